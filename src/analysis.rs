@@ -2,6 +2,7 @@ use crate::{memory::HashedMemory, segmentation::Hashment};
 use core::convert::From;
 use derive_more::{Add, AddAssign};
 use std::fmt::Debug;
+use std::collections::BTreeMap;
 use serde::{Serialize, Deserialize};
 
 /// Primitive struct to encapsulate the different analysis results.
@@ -39,7 +40,16 @@ pub struct Analysis {
     pub repetitions: Counts,
 
     /// The counts for segments matched with the translation memory.
-    pub matches: Counts
+    pub matches: Counts,
+
+    /// The total counts of segments that were extracted from a named part of the file, such as speaker notes.
+    /// These segments are also included in the other counts.
+    #[serde(default)]
+    pub parts: BTreeMap<String, Counts>,
+
+    /// Content that could not be counted, such as PDF pages without a text layer
+    #[serde(default)]
+    pub warnings: Vec<String>,
 }
 
 impl Analysis {
@@ -50,6 +60,8 @@ impl Analysis {
             total: Counts { segments: 0, words: 0, characters: 0 }, 
             repetitions: Counts { segments: 0, words: 0, characters: 0 },  
             matches: Counts { segments: 0, words: 0, characters: 0 },  
+            parts: BTreeMap::new(),
+            warnings: Vec::new(),
         }
     }
 }
@@ -76,10 +88,23 @@ pub fn analyze(hashments: &Vec<Hashment>, memory: &HashedMemory) -> Analysis {
     let mut total = Counts::default();
     let mut repetitions = Counts::default();
     let mut matches = Counts::default();
+    let mut parts: BTreeMap<String, Counts> = BTreeMap::new();
+    let mut warnings = Vec::new();
 
     for hashment in hashments {
+        if let Some(warning) = &hashment.warning {
+            warnings.push(warning.clone());
+        }
+        if hashment.words == 0 {
+            continue;
+        }
+
         total += Counts::from(hashment);
 
+        if let Some(part) = &hashment.part {
+            *parts.entry(part.clone()).or_default() += Counts::from(hashment);
+        }
+
         if memory.contains_hash(&hashment.hash) {
             matches += Counts::from(hashment);
         }
@@ -94,6 +119,8 @@ pub fn analyze(hashments: &Vec<Hashment>, memory: &HashedMemory) -> Analysis {
     Analysis {
         total,
         repetitions,
-        matches
+        matches,
+        parts,
+        warnings
     }
 }
\ No newline at end of file
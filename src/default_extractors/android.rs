@@ -0,0 +1,130 @@
+use std::error::Error;
+use std::str::from_utf8;
+use roxmltree::Node;
+use crate::{extract::{Extract, Section}, printf::strip_placeholders};
+
+/// Extraction rule for Android string resources (res/values/strings.xml).
+/// Strings, plurals and string arrays are extracted with their name as key, except resources marked with translatable="false".
+/// Content of xliff:g elements, format arguments such as %1$s and references to other resources are not counted.
+pub struct AndroidStrings;
+
+fn is_resources(buf: &[u8]) -> bool {
+    match from_utf8(buf).map(roxmltree::Document::parse) {
+        Ok(Ok(doc)) => doc.root_element().has_tag_name("resources"),
+        _ => false,
+    }
+}
+
+/// Resolves the escaping of Android string resources: backslash escapes, double quoted sections and collapsed whitespace
+fn unescape(s: &str) -> String {
+    let mut result = String::new();
+    let mut quoted = false;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('u') => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    if let Some(c) = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                        result.push(c);
+                    }
+                },
+                Some(c) => result.push(c),
+                None => (),
+            },
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !result.ends_with(' ') {
+                    result.push(' ');
+                }
+            },
+            c => result.push(c),
+        }
+    }
+    String::from(result.trim())
+}
+
+/// Removes the HTML markup that can be put in a string with CDATA, e.g. <![CDATA[<b>bold</b>]]>
+fn strip_markup(s: &str) -> String {
+    let mut result = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find('<') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('>') {
+            Some(end) if after.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') => rest = &after[end + 1..],
+            _ => {
+                result.push('<');
+                rest = after;
+            },
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The text of a resource value, leaving out the xliff:g placeholders
+fn value_text(node: Node) -> Option<String> {
+    let raw: String = node.descendants()
+        .filter(|n| n.is_text() && !n.ancestors().take_while(|a| *a != node).any(|a| a.has_tag_name("g")))
+        .filter_map(|n| n.text())
+        .collect();
+    // References such as @string/app_name point to a string that is counted on its own
+    if raw.trim_start().starts_with('@') {
+        return None;
+    }
+    let text = strip_placeholders(&unescape(&strip_markup(&raw)));
+    if text.trim().is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+impl Extract for AndroidStrings {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("xml") && is_resources(buf)
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let str = from_utf8(buf)?;
+        let doc = roxmltree::Document::parse(str)?;
+        let mut vec = Vec::new();
+        for resource in doc.root_element().children().filter(|n| n.is_element()) {
+            if resource.attribute("translatable") == Some("false") {
+                continue;
+            }
+            let name = resource.attribute("name").unwrap_or("");
+            match resource.tag_name().name() {
+                "string" => {
+                    if let Some(text) = value_text(resource) {
+                        vec.push(Section { text, key: Some(String::from(name)), ..Default::default() });
+                    }
+                },
+                "plurals" => {
+                    for item in resource.children().filter(|n| n.has_tag_name("item")) {
+                        if let Some(text) = value_text(item) {
+                            vec.push(Section {
+                                text,
+                                key: Some(String::from(name)),
+                                context: item.attribute("quantity").map(|q| format!("plural form: {}", q)),
+                                ..Default::default()
+                            });
+                        }
+                    }
+                },
+                "string-array" => {
+                    for (i, item) in resource.children().filter(|n| n.has_tag_name("item")).enumerate() {
+                        if let Some(text) = value_text(item) {
+                            vec.push(Section { text, key: Some(format!("{}[{}]", name, i)), ..Default::default() });
+                        }
+                    }
+                },
+                _ => (),
+            }
+        }
+        Ok(vec)
+    }
+}
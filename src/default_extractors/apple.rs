@@ -0,0 +1,338 @@
+use std::error::Error;
+use std::str::from_utf8;
+use roxmltree::Node;
+use serde_json::Value;
+use crate::{extract::{Extract, Section}, printf::strip_placeholders};
+use super::json::parse;
+
+/// Extraction rule for Apple .strings files, which can be encoded in UTF-8 or UTF-16.
+/// Every value is extracted with its key, the comment above an entry is used as context. Format arguments such as %@ are not counted.
+pub struct AppleStrings;
+
+/// Extraction rule for Apple .stringsdict files. Every plural (or device or width) variant is extracted as a separate section.
+pub struct StringsDict;
+
+/// Extraction rule for Apple String Catalogs (.xcstrings). Only the strings that still need translation are counted.
+/// Strings marked as "Don't translate" and stale strings that are no longer used in the code are not counted either.
+#[derive(Default)]
+pub struct StringCatalog {
+    /// Only count the strings that are not yet translated into this language, e.g. "de".
+    /// When empty, the strings that are not yet translated into one of the languages of the catalog are counted.
+    pub language: Option<String>,
+}
+
+/// Decodes a text file that is either UTF-16 or UTF-8, with or without a byte order mark
+fn decode(buf: &[u8]) -> Result<String, Box<dyn Error>> {
+    let utf16 = |big_endian: bool, buf: &[u8]| -> Result<String, Box<dyn Error>> {
+        let units: Vec<u16> = buf.chunks_exact(2)
+            .map(|b| if big_endian { u16::from_be_bytes([b[0], b[1]]) } else { u16::from_le_bytes([b[0], b[1]]) })
+            .collect();
+        Ok(String::from_utf16(&units)?)
+    };
+    match buf {
+        [0xFF, 0xFE, rest @ ..] => utf16(false, rest),
+        [0xFE, 0xFF, rest @ ..] => utf16(true, rest),
+        [0xEF, 0xBB, 0xBF, rest @ ..] => Ok(String::from(from_utf8(rest)?)),
+        [_, 0, ..] => utf16(false, buf),
+        [0, _, ..] => utf16(true, buf),
+        _ => Ok(String::from(from_utf8(buf)?)),
+    }
+}
+
+enum Token {
+    Comment(String),
+    String(String),
+    Equals,
+    Semicolon,
+}
+
+fn read_quoted(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut s = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => match chars.next() {
+                Some('n') => s.push('\n'),
+                Some('t') => s.push('\t'),
+                Some('r') => s.push('\r'),
+                Some('u') | Some('U') => {
+                    let code: String = chars.by_ref().take(4).collect();
+                    if let Some(c) = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                        s.push(c);
+                    }
+                },
+                Some(c) => s.push(c),
+                None => (),
+            },
+            c => s.push(c),
+        }
+    }
+    s
+}
+
+fn tokenize(s: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '=' => tokens.push(Token::Equals),
+            ';' => tokens.push(Token::Semicolon),
+            '"' => tokens.push(Token::String(read_quoted(&mut chars))),
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut comment = String::new();
+                while let Some(c) = chars.next() {
+                    if c == '*' && chars.peek() == Some(&'/') {
+                        chars.next();
+                        break;
+                    }
+                    comment.push(c);
+                }
+                tokens.push(Token::Comment(String::from(comment.trim())));
+            },
+            '/' if chars.peek() == Some(&'/') => {
+                let comment: String = chars.by_ref().take_while(|c| *c != '\n').collect();
+                tokens.push(Token::Comment(String::from(comment.trim_start_matches('/').trim())));
+            },
+            c if c.is_whitespace() => (),
+            // Unquoted keys and values
+            c => {
+                let mut word = String::from(c);
+                while let Some(c) = chars.peek().copied().filter(|c| !c.is_whitespace() && *c != '=' && *c != ';') {
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::String(word));
+            },
+        }
+    }
+    tokens
+}
+
+impl Extract for AppleStrings {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("strings")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let s = decode(buf)?;
+        let mut vec = Vec::new();
+        let mut comment = None;
+        let mut strings = Vec::new();
+        for token in tokenize(&s) {
+            match token {
+                Token::Comment(c) => comment = Some(c),
+                Token::String(s) => strings.push(s),
+                Token::Equals => (),
+                Token::Semicolon => {
+                    let context = comment.take();
+                    let mut strings = std::mem::take(&mut strings).into_iter();
+                    let key = strings.next();
+                    // An entry without a value ("key";) uses the key as value
+                    if let Some(value) = strings.next().or_else(|| key.clone()) {
+                        let text = strip_placeholders(&value);
+                        if !text.trim().is_empty() {
+                            vec.push(Section { text, key, context, ..Default::default() });
+                        }
+                    }
+                },
+            }
+        }
+        Ok(vec)
+    }
+}
+
+/// The key and value pairs of a property list dict
+fn dict_entries<'a, 'input>(dict: Node<'a, 'input>) -> Vec<(&'a str, Node<'a, 'input>)> {
+    let mut vec = Vec::new();
+    let mut key = None;
+    for node in dict.children().filter(|n| n.is_element()) {
+        if node.has_tag_name("key") {
+            key = node.text();
+        } else if let Some(k) = key.take() {
+            vec.push((k, node));
+        }
+    }
+    vec
+}
+
+fn extract_rule(key: &str, dict: Node, context: Option<String>, vec: &mut Vec<Section>) {
+    let entries = dict_entries(dict);
+    let rule_type = entries.iter().find(|(k, _)| *k == "NSStringFormatSpecTypeKey").and_then(|(_, v)| v.text());
+    let label = match rule_type {
+        Some("NSStringPluralRuleType") => "plural form",
+        Some("NSStringDeviceSpecificRuleType") => "device",
+        Some("NSStringVariableWidthRuleType") => "width",
+        _ => "",
+    };
+    for (name, value) in entries {
+        let context = match name {
+            "NSStringFormatSpecTypeKey" | "NSStringFormatValueTypeKey" => continue,
+            "NSStringLocalizedFormatKey" => context.clone(),
+            _ if label.is_empty() => context.clone(),
+            form => Some(format!("{}: {}", label, form)),
+        };
+        if value.has_tag_name("dict") {
+            extract_rule(key, value, context, vec);
+        } else if let Some(text) = value.text().map(strip_placeholders) {
+            if !text.trim().is_empty() {
+                vec.push(Section { text, key: Some(String::from(key)), context, ..Default::default() });
+            }
+        }
+    }
+}
+
+impl Extract for StringsDict {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("stringsdict")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let s = decode(buf)?;
+        let doc = roxmltree::Document::parse(&s)?;
+        let mut vec = Vec::new();
+        if let Some(root) = doc.root_element().children().find(|n| n.has_tag_name("dict")) {
+            for (key, dict) in dict_entries(root) {
+                extract_rule(key, dict, None, &mut vec);
+            }
+        }
+        Ok(vec)
+    }
+}
+
+/// A string unit of a String Catalog localization: its value, state and variation
+struct StringUnit {
+    text: String,
+    state: Option<String>,
+    variation: Option<String>,
+}
+
+fn string_units(v: &Value, variation: Option<String>, vec: &mut Vec<StringUnit>) {
+    if let Some(unit) = v.get("stringUnit") {
+        vec.push(StringUnit {
+            text: unit.get("value").and_then(Value::as_str).map(String::from).unwrap_or_default(),
+            state: unit.get("state").and_then(Value::as_str).map(String::from),
+            variation: variation.clone(),
+        });
+    }
+    for (kind, forms) in v.get("variations").and_then(Value::as_object).into_iter().flatten() {
+        let label = if kind == "plural" { "plural form" } else { kind.as_str() };
+        for (form, v) in forms.as_object().into_iter().flatten() {
+            string_units(v, Some(format!("{}: {}", label, form)), vec);
+        }
+    }
+    for substitution in v.get("substitutions").and_then(Value::as_object).into_iter().flatten().map(|(_, s)| s) {
+        string_units(substitution, variation.clone(), vec);
+    }
+}
+
+impl StringCatalog {
+    /// The languages to translate into: the configured language, or all languages in the catalog except the source language
+    fn target_languages(&self, catalog: &Value, source_language: &str) -> Vec<String> {
+        if let Some(language) = &self.language {
+            return vec![language.clone()];
+        }
+        let mut languages: Vec<String> = catalog.get("strings").and_then(Value::as_object).into_iter().flatten()
+            .filter_map(|(_, entry)| entry.get("localizations").and_then(Value::as_object))
+            .flat_map(|l| l.keys())
+            .filter(|l| *l != source_language)
+            .cloned()
+            .collect();
+        languages.sort();
+        languages.dedup();
+        languages
+    }
+
+    /// Whether every string unit of the entry is translated into all the languages. A catalog without target languages has nothing translated.
+    fn is_translated(entry: &Value, languages: &[String]) -> bool {
+        !languages.is_empty() && languages.iter().all(|language| {
+            let mut units = Vec::new();
+            if let Some(l) = entry.get("localizations").and_then(|l| l.get(language)) {
+                string_units(l, None, &mut units);
+            }
+            !units.is_empty() && units.iter().all(|u| u.state.as_deref() == Some("translated"))
+        })
+    }
+}
+
+impl Extract for StringCatalog {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("xcstrings")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let catalog = parse(buf)?;
+        let source_language = catalog.get("sourceLanguage").and_then(Value::as_str).unwrap_or("en");
+        let languages = self.target_languages(&catalog, source_language);
+        let mut vec = Vec::new();
+        for (key, entry) in catalog.get("strings").and_then(Value::as_object).into_iter().flatten() {
+            if entry.get("shouldTranslate") == Some(&Value::Bool(false))
+                || entry.get("extractionState").and_then(Value::as_str) == Some("stale")
+                || StringCatalog::is_translated(entry, &languages) {
+                continue;
+            }
+            let comment = entry.get("comment").and_then(Value::as_str).map(String::from);
+            let mut units = Vec::new();
+            if let Some(source) = entry.get("localizations").and_then(|l| l.get(source_language)) {
+                string_units(source, None, &mut units);
+            }
+            // Strings that are only in the code have no source localization, the key is the source text
+            if units.is_empty() {
+                units.push(StringUnit { text: key.clone(), state: None, variation: None });
+            }
+            for unit in units {
+                let text = strip_placeholders(&unit.text);
+                if !text.trim().is_empty() {
+                    vec.push(Section { text, key: Some(key.clone()), context: unit.variation.or_else(|| comment.clone()), ..Default::default() });
+                }
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CATALOG: &str = r#"{
+        "sourceLanguage": "en",
+        "strings": {
+            "Hello": { "localizations": { "de": { "stringUnit": { "state": "translated", "value": "Hallo" } } } },
+            "Goodbye": {},
+            "Review": { "localizations": { "de": { "stringUnit": { "state": "needs_review", "value": "Prüfen" } } } },
+            "%lld files": { "localizations": {
+                "en": { "variations": { "plural": {
+                    "one": { "stringUnit": { "state": "new", "value": "%lld file" } },
+                    "other": { "stringUnit": { "state": "new", "value": "%lld files" } }
+                } } },
+                "de": { "variations": { "plural": {
+                    "one": { "stringUnit": { "state": "translated", "value": "%lld Datei" } },
+                    "other": { "stringUnit": { "state": "new", "value": "%lld Dateien" } }
+                } } }
+            } },
+            "Internal": { "shouldTranslate": false }
+        },
+        "version": "1.0"
+    }"#;
+
+    fn keys(catalog: &StringCatalog) -> Vec<String> {
+        let mut keys: Vec<String> = catalog.extract_sections(CATALOG.as_bytes()).unwrap().into_iter().filter_map(|s| s.key).collect();
+        keys.dedup();
+        keys
+    }
+
+    #[test]
+    fn counts_only_untranslated_entries_of_half_translated_catalog() {
+        assert_eq!(keys(&StringCatalog::default()), vec!["Goodbye", "Review", "%lld files"]);
+        let sections = StringCatalog::default().extract_sections(CATALOG.as_bytes()).unwrap();
+        let plural: Vec<(&str, Option<&str>)> = sections.iter().filter(|s| s.key.as_deref() == Some("%lld files")).map(|s| (s.text.as_str(), s.context.as_deref())).collect();
+        assert_eq!(plural, vec![(" file", Some("plural form: one")), (" files", Some("plural form: other"))]);
+    }
+
+    #[test]
+    fn counts_entries_not_translated_into_language() {
+        let catalog = StringCatalog { language: Some(String::from("fr")) };
+        assert_eq!(keys(&catalog), vec!["Hello", "Goodbye", "Review", "%lld files"]);
+    }
+}
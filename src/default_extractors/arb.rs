@@ -0,0 +1,65 @@
+use std::error::Error;
+use crate::{extract::{Extract, Section}, icu::expand};
+use super::json::parse;
+use serde_json::Value;
+
+/// Extraction rule for Application Resource Bundle (.arb) files used by Flutter.
+/// Messages are ICU MessageFormat, so plural and select branches are extracted as separate variants.
+/// The @ metadata entries are not counted, but their description is used as context.
+pub struct Arb;
+
+impl Extract for Arb {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("arb")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let v = parse(buf)?;
+        let map = match v.as_object() {
+            Some(map) => map,
+            None => return Ok(Vec::new()),
+        };
+        let mut vec = Vec::new();
+        for (key, message) in map {
+            let message = match message.as_str() {
+                Some(m) if !key.starts_with('@') => m,
+                _ => continue,
+            };
+            let description = map.get(&format!("@{}", key))
+                .and_then(|m| m.get("description"))
+                .and_then(Value::as_str);
+            for text in expand(message) {
+                vec.push(Section {
+                    text,
+                    key: Some(key.clone()),
+                    context: description.map(String::from),
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_messages_and_skips_metadata() {
+        let json = br#"{
+            "@@locale": "en",
+            "title": "My app",
+            "@title": {"description": "The title of the app"},
+            "inbox": "{count, plural, =0 {No messages} other {{count} messages}}",
+            "@inbox": {"placeholders": {"count": {"type": "int"}}}
+        }"#;
+        let sections = Arb.extract_sections(json).unwrap();
+        let sections: Vec<(&str, Option<&str>, Option<&str>)> = sections.iter().map(|s| (s.text.as_str(), s.key.as_deref(), s.context.as_deref())).collect();
+        assert_eq!(sections, vec![
+            ("My app", Some("title"), Some("The title of the app")),
+            ("No messages", Some("inbox"), None),
+            (" messages", Some("inbox"), None),
+        ]);
+    }
+}
@@ -0,0 +1,82 @@
+use std::error::Error;
+use crate::extract::{Extract, Section};
+use super::json::parse;
+use serde_json::Value;
+
+/// Extraction rule for the messages.json files of Chrome extensions (_locales/en/messages.json).
+/// Only the message of every entry is extracted, the description is used as context. $PLACEHOLDERS$ are not counted.
+pub struct ChromeMessages;
+
+/// Whether every entry in the file is an object with a message
+fn looks_like_messages(v: &Value) -> bool {
+    match v.as_object() {
+        Some(map) => !map.is_empty() && map.values().all(|entry| entry.get("message").is_some_and(Value::is_string)),
+        None => false,
+    }
+}
+
+/// Removes $NAME$ placeholders and $1 substitutions
+fn strip_placeholders(s: &str) -> String {
+    let mut result = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_length = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+        rest = if after[name_length..].starts_with('$') && name_length > 0 {
+            &after[name_length + 1..]
+        } else if after.starts_with(|c: char| c.is_ascii_digit()) {
+            &after[1..]
+        } else {
+            result.push('$');
+            after
+        };
+    }
+    result.push_str(rest);
+    result
+}
+
+impl Extract for ChromeMessages {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("json") && parse(buf).is_ok_and(|v| looks_like_messages(&v))
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let v = parse(buf)?;
+        let mut vec = Vec::new();
+        for (key, entry) in v.as_object().into_iter().flatten() {
+            if let Some(message) = entry.get("message").and_then(Value::as_str) {
+                vec.push(Section {
+                    text: strip_placeholders(message),
+                    key: Some(key.clone()),
+                    context: entry.get("description").and_then(Value::as_str).map(String::from),
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_messages_with_descriptions() {
+        let json = br#"{
+            "appName": {"message": "Tab Saver", "description": "The name of the extension"},
+            "saved": {"message": "Saved $COUNT$ tabs for $1", "placeholders": {"count": {"content": "$1"}}},
+            "price": {"message": "Costs $5 or $ more"}
+        }"#;
+        assert!(ChromeMessages.can_extract(json, Some("json")));
+        assert!(!ChromeMessages.can_extract(br#"{"appName": "Tab Saver"}"#, Some("json")));
+        let sections = ChromeMessages.extract_sections(json).unwrap();
+        let sections: Vec<(&str, Option<&str>, Option<&str>)> = sections.iter().map(|s| (s.text.as_str(), s.key.as_deref(), s.context.as_deref())).collect();
+        assert_eq!(sections, vec![
+            ("Tab Saver", Some("appName"), Some("The name of the extension")),
+            ("Saved  tabs for ", Some("saved"), None),
+            ("Costs  or $ more", Some("price"), None),
+        ]);
+    }
+}
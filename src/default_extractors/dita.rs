@@ -0,0 +1,80 @@
+use std::error::Error;
+use std::str::from_utf8;
+use crate::extract::{Extract, Section};
+use super::xml::{ItsRules, Xml};
+
+/// Extraction rule for DITA topics and maps (.dita, .ditamap and .xml files with a DITA doctype).
+/// Code, commands and file paths are not counted, neither are elements that pull in their content with conref or keyref,
+/// as that content is counted where it is defined. Inline elements such as ph, b and uicontrol stay part of the sentence.
+pub struct Dita {
+    /// The XML extraction rule with the DITA preset, which can be extended with custom ITS rules
+    pub xml: Xml,
+}
+
+const DITA_RULES: &str = r#"<its:rules xmlns:its="http://www.w3.org/2005/11/its" version="2.0">
+    <its:translateRule selector="//codeblock | //codeph | //cmdname | //filepath | //apiname | //parmname | //coderef
+        | //msgblock | //msgph | //msgnum | //screen | //systemoutput | //draft-comment | //required-cleanup | //data | //unknown | //foreign" translate="no"/>
+    <its:translateRule selector="//*[@conref] | //*[@conkeyref]" translate="no"/>
+    <its:translateRule selector="//keyword[@keyref] | //ph[@keyref] | //term[@keyref] | //abbreviated-form[@keyref]" translate="no"/>
+    <its:translateRule selector="//*[@translate='no']" translate="no"/>
+    <its:translateRule selector="//*[@translate='yes']" translate="yes"/>
+    <its:translateRule selector="//topicref/@navtitle | //image/@alt" translate="yes"/>
+    <its:withinTextRule selector="//fn | //indexterm" withinText="nested"/>
+    <its:idValueRule selector="//*[@id]" idValue="@id"/>
+</its:rules>"#;
+
+/// Elements that are part of the text of their parent
+const DITA_INLINE: [&str; 40] = [
+    "b", "i", "u", "sup", "sub", "tt", "line-through", "overline", "ph", "keyword", "term", "xref", "codeph", "cmdname", "filepath",
+    "apiname", "parmname", "varname", "option", "synph", "uicontrol", "menucascade", "wintitle", "userinput", "systemoutput",
+    "msgph", "msgnum", "q", "cite", "tm", "abbreviated-form", "text", "image", "data", "draft-comment", "required-cleanup",
+    "boolean", "state", "indextermref", "linktext",
+];
+
+impl Default for Dita {
+    fn default() -> Self {
+        Dita {
+            xml: Xml {
+                rules: ItsRules::from_xml(DITA_RULES.as_bytes()).unwrap_or_default(),
+                elements_within_text: DITA_INLINE.iter().map(|e| String::from(*e)).collect(),
+            },
+        }
+    }
+}
+
+fn is_dita(buf: &[u8]) -> bool {
+    match from_utf8(buf) {
+        Ok(s) => s.contains("-//OASIS//DTD DITA") || s.contains("http://dita.oasis-open.org/architecture/2005/"),
+        Err(_) => false,
+    }
+}
+
+impl Extract for Dita {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("dita") || extension == Some("ditamap") || (extension == Some("xml") && is_dita(buf))
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        self.xml.extract_sections(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_code_and_conrefs() {
+        let dita = br#"<?xml version="1.0"?>
+<!DOCTYPE topic PUBLIC "-//OASIS//DTD DITA Topic//EN" "topic.dtd">
+<topic id="install"><title>Install</title><body>
+    <p>Click <uicontrol>Save</uicontrol> to keep the <b>file</b><indexterm>saving</indexterm>.</p>
+    <codeblock>cargo install filecount</codeblock>
+    <p conref="common.dita#common/warning">Reused warning</p>
+    <p>Run <cmdname>filecount</cmdname> in <filepath>/tmp</filepath> for the <keyword keyref="product">product</keyword>.</p>
+    <image href="a.png" alt="Save button"/>
+</body></topic>"#;
+        assert!(Dita::default().can_extract(dita, Some("xml")));
+        assert_eq!(Dita::default().extract(dita).unwrap(), vec!["Install", "Click Save to keep the file.", "saving", "Run in for the .", "Save button"]);
+    }
+}
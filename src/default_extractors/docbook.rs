@@ -0,0 +1,75 @@
+use std::error::Error;
+use std::str::from_utf8;
+use crate::extract::{Extract, Section};
+use super::xml::{ItsRules, Xml};
+
+/// Extraction rule for DocBook 4 and 5 documents (.dbk and .xml files with the DocBook namespace or doctype).
+/// Program listings, screens, synopses and other code are not counted, neither are remarks.
+/// Inline elements such as emphasis, link and guilabel stay part of the sentence, footnotes and index terms are separate sections.
+pub struct DocBook {
+    /// The XML extraction rule with the DocBook preset, which can be extended with custom ITS rules
+    pub xml: Xml,
+}
+
+const DOCBOOK_RULES: &str = r#"<its:rules xmlns:its="http://www.w3.org/2005/11/its" version="2.0">
+    <its:translateRule selector="//programlisting | //screen | //synopsis | //cmdsynopsis | //funcsynopsis | //classsynopsis
+        | //literal | //code | //command | //computeroutput | //userinput | //filename | //envar | //systemitem | //markup | //tag
+        | //classname | //methodname | //function | //parameter | //varname | //constant | //prompt | //uri | //email | //remark" translate="no"/>
+    <its:withinTextRule selector="//footnote | //indexterm" withinText="nested"/>
+    <its:idValueRule selector="//*[@id]" idValue="@id"/>
+</its:rules>"#;
+
+/// Elements that are part of the text of their parent
+const DOCBOOK_INLINE: [&str; 56] = [
+    "emphasis", "phrase", "link", "xref", "ulink", "olink", "citetitle", "quote", "literal", "code", "command", "filename",
+    "option", "userinput", "computeroutput", "replaceable", "guilabel", "guibutton", "guiicon", "guimenu", "guimenuitem",
+    "guisubmenu", "menuchoice", "keycap", "keycombo", "acronym", "abbrev", "trademark", "productname", "firstterm", "glossterm",
+    "foreignphrase", "wordasword", "superscript", "subscript", "inlinemediaobject", "envar", "systemitem", "markup", "tag",
+    "classname", "methodname", "function", "parameter", "varname", "constant", "prompt", "footnoteref", "anchor", "email", "uri",
+    "application", "errorcode", "hardware", "package", "remark",
+];
+
+impl Default for DocBook {
+    fn default() -> Self {
+        DocBook {
+            xml: Xml {
+                rules: ItsRules::from_xml(DOCBOOK_RULES.as_bytes()).unwrap_or_default(),
+                elements_within_text: DOCBOOK_INLINE.iter().map(|e| String::from(*e)).collect(),
+            },
+        }
+    }
+}
+
+fn is_docbook(buf: &[u8]) -> bool {
+    match from_utf8(buf) {
+        Ok(s) => s.contains("http://docbook.org/ns/docbook") || s.contains("-//OASIS//DTD DocBook"),
+        Err(_) => false,
+    }
+}
+
+impl Extract for DocBook {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("dbk") || (extension == Some("xml") && is_docbook(buf))
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        self.xml.extract_sections(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_program_listings_and_remarks() {
+        let docbook = br#"<?xml version="1.0"?>
+<article xmlns="http://docbook.org/ns/docbook" version="5.0"><title>Setup</title>
+    <para>Choose <guimenu>File</guimenu> and <emphasis>Open</emphasis><indexterm><primary>opening</primary></indexterm>.<footnote><para>Or press a key.</para></footnote></para>
+    <programlisting>fn main() {}</programlisting>
+    <para>Edit <filename>config.toml</filename> with <command>vim</command>.<remark>Check this</remark></para>
+</article>"#;
+        assert!(DocBook::default().can_extract(docbook, Some("xml")));
+        assert_eq!(DocBook::default().extract(docbook).unwrap(), vec!["Setup", "Choose File and Open.", "opening", "Or press a key.", "Edit with ."]);
+    }
+}
@@ -1,33 +1,178 @@
 use std::error::Error;
-use crate::{extract::Extract, zip_extensions::read_file_from_zip};
+use roxmltree::Node;
+use crate::{extract::Extract, zip_extensions::{read_file_from_zip, read_files_from_zip}, xml_extensions::extract_drawing_paragraphs};
 
-pub struct Docx;
+/// Extraction rule for .docx files. The main document is always extracted, the other parts can be switched on and off.
+pub struct Docx {
+    /// Extract the page headers and footers
+    pub headers_footers: bool,
 
-impl Extract for Docx {
-    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
-        extension == Some("docx") || infer::doc::is_docx(buf)
+    /// Extract footnotes and endnotes
+    pub notes: bool,
+
+    /// Extract review comments
+    pub comments: bool,
+
+    /// Extract text boxes and shapes placed in the document
+    pub text_boxes: bool,
+
+    /// Extract titles, series names and category labels of charts
+    pub charts: bool,
+
+    /// Extract the text of SmartArt diagrams
+    pub diagrams: bool,
+
+    /// Extract runs that are formatted as hidden text
+    pub hidden: bool,
+}
+
+impl Default for Docx {
+    fn default() -> Self {
+        Docx {
+            headers_footers: true,
+            notes: true,
+            comments: false,
+            text_boxes: true,
+            charts: true,
+            diagrams: true,
+            hidden: false,
+        }
     }
+}
 
-    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
-        let file = read_file_from_zip(buf, "word/document.xml")?;
-        let doc = roxmltree::Document::parse(&file)?;
+const W_NS: &str = "http://schemas.openxmlformats.org/wordprocessingml/2006/main";
+
+/// Whether a run is formatted as hidden text (w:vanish)
+fn is_vanish(run: Node) -> bool {
+    run.children()
+        .filter(|n| n.has_tag_name((W_NS, "rPr")))
+        .flat_map(|n| n.children())
+        .any(|n| n.has_tag_name((W_NS, "vanish")) && !matches!(n.attribute((W_NS, "val")), Some("0") | Some("false") | Some("off")))
+}
+
+impl Docx {
+    /// Concatenates the text of a paragraph. Deleted revisions and field instructions are skipped while field results are kept.
+    /// Paragraphs nested in text boxes are left out here, as they are extracted on their own.
+    fn paragraph_text(&self, node: Node, s: &mut String) {
+        for child in node.children().filter(|n| n.is_element()) {
+            match child.tag_name().name() {
+                "t" => {
+                    if let Some(t) = child.text() {
+                        *s += t;
+                    }
+                },
+                "tab" => *s += "  ",
+                "del" | "moveFrom" | "delText" | "instrText" | "pPr" | "rPr" | "Fallback" | "txbxContent" => (),
+                "r" if !self.hidden && is_vanish(child) => (),
+                _ => self.paragraph_text(child, s),
+            }
+        }
+    }
+
+    fn extract_paragraphs(&self, xml: &str) -> Result<Vec<String>, Box<dyn Error>> {
+        let doc = roxmltree::Document::parse(xml)?;
         let paragraphs = doc.root().descendants().filter(|n| n.has_tag_name("p"));
         let mut vec = Vec::new();
         for paragraph in paragraphs {
-            let mut s = String::new();
-            for node in paragraph.descendants() {
-                if node.is_text() {
-                    match node.text() {
-                        Some(t) => s += t,
-                        None => (),
-                    }
-                } else if node.has_tag_name("tab") {
-                    s += "  ";
-                }
+            // Text boxes are stored twice, as a DrawingML shape and as a VML fallback for older versions of Word
+            if paragraph.ancestors().any(|n| n.has_tag_name("Fallback")) {
+                continue;
+            }
+            if !self.text_boxes && paragraph.ancestors().any(|n| n.has_tag_name("txbxContent")) {
+                continue;
             }
+            let mut s = String::new();
+            self.paragraph_text(paragraph, &mut s);
             vec.push(s);
         }
 
         Ok(vec)
     }
-}
\ No newline at end of file
+}
+
+impl Extract for Docx {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("docx") || (extension.is_none() && infer::doc::is_docx(buf))
+    }
+
+    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        let file = read_file_from_zip(buf, "word/document.xml")?;
+        let mut vec = self.extract_paragraphs(&file)?;
+
+        let mut parts = Vec::new();
+        if self.headers_footers {
+            parts.append(&mut read_files_from_zip(buf, "word/header")?);
+            parts.append(&mut read_files_from_zip(buf, "word/footer")?);
+        }
+        if self.notes {
+            parts.append(&mut read_files_from_zip(buf, "word/footnotes.xml")?);
+            parts.append(&mut read_files_from_zip(buf, "word/endnotes.xml")?);
+        }
+        if self.comments {
+            parts.append(&mut read_files_from_zip(buf, "word/comments.xml")?);
+        }
+        for part in parts {
+            vec.append(&mut self.extract_paragraphs(&part)?);
+        }
+
+        let mut drawings = Vec::new();
+        if self.charts {
+            drawings.append(&mut read_files_from_zip(buf, "word/charts/chart")?);
+        }
+        if self.diagrams {
+            drawings.append(&mut read_files_from_zip(buf, "word/diagrams/data")?);
+        }
+        for drawing in drawings {
+            let doc = roxmltree::Document::parse(&drawing)?;
+            vec.append(&mut extract_drawing_paragraphs(doc.root()));
+        }
+
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip_extensions::build_zip;
+
+    fn part(root: &str, body: &str) -> String {
+        format!(r#"<w:{0} xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"
+            xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006">{1}</w:{0}>"#, root, body)
+    }
+
+    #[test]
+    fn extracts_switchable_parts() {
+        let document = part("document", "<w:body><w:p><w:r><w:t>Body text</w:t></w:r></w:p></w:body>");
+        let buf = build_zip(&[
+            ("word/document.xml", &document),
+            ("word/header1.xml", &part("hdr", "<w:p><w:r><w:t>Header</w:t></w:r></w:p>")),
+            ("word/footnotes.xml", &part("footnotes", "<w:footnote><w:p><w:r><w:t>Footnote</w:t></w:r></w:p></w:footnote>")),
+            ("word/comments.xml", &part("comments", "<w:comment><w:p><w:r><w:t>Comment</w:t></w:r></w:p></w:comment>")),
+        ]);
+        assert_eq!(Docx::default().extract(&buf).unwrap(), vec!["Body text", "Header", "Footnote"]);
+
+        let docx = Docx { headers_footers: false, notes: false, comments: true, ..Default::default() };
+        assert_eq!(docx.extract(&buf).unwrap(), vec!["Body text", "Comment"]);
+    }
+
+    #[test]
+    fn skips_deletions_field_codes_and_hidden_runs() {
+        let document = part("document", r#"<w:body>
+            <w:p><w:r><w:t xml:space="preserve">Keep </w:t></w:r><w:del><w:r><w:delText>deleted </w:delText></w:r></w:del><w:ins><w:r><w:t>inserted</w:t></w:r></w:ins></w:p>
+            <w:p><w:moveFrom><w:r><w:t>Moved</w:t></w:r></w:moveFrom></w:p>
+            <w:p><w:moveTo><w:r><w:t>Moved</w:t></w:r></w:moveTo></w:p>
+            <w:p><w:r><w:fldChar w:fldCharType="begin"/></w:r><w:r><w:instrText> PAGE </w:instrText></w:r><w:r><w:fldChar w:fldCharType="separate"/></w:r><w:r><w:t>1</w:t></w:r><w:r><w:fldChar w:fldCharType="end"/></w:r></w:p>
+            <w:p><w:r><w:t xml:space="preserve">Shown </w:t></w:r><w:r><w:rPr><w:vanish/></w:rPr><w:t>hidden</w:t></w:r></w:p>
+            <w:p><w:r><w:t>Before box</w:t></w:r><w:r><mc:AlternateContent>
+                <mc:Choice Requires="wps"><w:drawing><w:txbxContent><w:p><w:r><w:t>Text box</w:t></w:r></w:p></w:txbxContent></w:drawing></mc:Choice>
+                <mc:Fallback><w:pict><w:txbxContent><w:p><w:r><w:t>Text box</w:t></w:r></w:p></w:txbxContent></w:pict></mc:Fallback>
+            </mc:AlternateContent></w:r></w:p>
+        </w:body>"#);
+        let buf = build_zip(&[("word/document.xml", &document)]);
+        assert_eq!(Docx::default().extract(&buf).unwrap(), vec!["Keep inserted", "", "Moved", "1", "Shown ", "Before box", "Text box"]);
+
+        let docx = Docx { hidden: true, ..Default::default() };
+        assert_eq!(docx.extract(&buf).unwrap()[4], "Shown hidden");
+    }
+}
@@ -0,0 +1,246 @@
+use std::error::Error;
+use crate::{extract::{Extract, Section}, zip_extensions::{read_file_from_zip, resolve_path}};
+use super::html::extract_body;
+use std::str::from_utf8;
+
+/// Extraction rule for .epub publications. The XHTML content documents are extracted in the reading order of the spine in the package (OPF) document,
+/// the same way as [HTML](super::html::Html) files. Stylesheets, fonts and images are never extracted.
+/// Content documents that are missing or can't be read are skipped and listed in the warnings of the [analysis](crate::analysis::Analysis).
+pub struct Epub {
+    /// Extract the title and description of the publication. Metadata is reported as the "metadata" part in the [analysis](crate::analysis::Analysis).
+    pub metadata: bool,
+
+    /// Extract the table of contents of the navigation document (or the NCX table of contents of EPUB 2). Landmarks and page lists are not extracted.
+    /// Reported as the "navigation" part in the [analysis](crate::analysis::Analysis).
+    pub navigation: bool,
+}
+
+impl Default for Epub {
+    fn default() -> Self {
+        Epub {
+            metadata: true,
+            navigation: true,
+        }
+    }
+}
+
+const OPF_NS: &str = "http://www.idpf.org/2007/opf";
+const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+const OPS_NS: &str = "http://www.idpf.org/2007/ops";
+
+/// Metadata elements with translatable text
+const METADATA_ELEMENTS: [&str; 2] = ["title", "description"];
+
+/// An item in the manifest of the package document
+struct Item<'a> {
+    id: &'a str,
+    /// The path of the item within the archive
+    path: String,
+    media_type: &'a str,
+    properties: &'a str,
+}
+
+/// The path of the package document, as listed in META-INF/container.xml
+fn package_path(buf: &[u8]) -> Result<String, Box<dyn Error>> {
+    let container = read_file_from_zip(buf, "META-INF/container.xml")?;
+    let doc = roxmltree::Document::parse(&container)?;
+    let path = doc.descendants()
+        .filter(|n| n.has_tag_name("rootfile"))
+        .find(|n| n.attribute("media-type").unwrap_or("application/oebps-package+xml") == "application/oebps-package+xml")
+        .and_then(|n| n.attribute("full-path"))
+        .ok_or("No package document in META-INF/container.xml")?;
+    Ok(String::from(path))
+}
+
+/// Decodes the %XX escapes in the href of a manifest item, e.g. chapter%201.xhtml
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|h| from_utf8(h).ok()).and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+            },
+            (b, _) => {
+                decoded.push(b);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Extracts the body of an XHTML content document, which the HTML parser reads without its XML declaration
+fn extract_content_document(buf: &[u8], path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let content = read_file_from_zip(buf, path)?;
+    let content = content.trim_start_matches('\u{feff}').trim_start();
+    let content = match content.strip_prefix("<?xml").and_then(|c| c.split_once("?>")) {
+        Some((_, rest)) => rest,
+        None => content,
+    };
+    extract_body(content)
+}
+
+/// The heading and the labels of the entries of the table of contents (nav epub:type="toc") in the navigation document
+fn extract_nav(buf: &[u8], path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let nav = read_file_from_zip(buf, path)?;
+    let doc = roxmltree::Document::parse(&nav)?;
+    let toc = doc.descendants()
+        .filter(|n| n.has_tag_name("nav"))
+        .find(|n| n.attribute((OPS_NS, "type")).is_some_and(|t| t.split_whitespace().any(|t| t == "toc")));
+    let toc = match toc {
+        Some(toc) => toc,
+        None => return Ok(Vec::new()),
+    };
+    Ok(toc.descendants()
+        .filter(|n| matches!(n.tag_name().name(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "a" | "span"))
+        .filter(|n| !n.ancestors().skip(1).any(|a| matches!(a.tag_name().name(), "a" | "span")))
+        .map(|n| n.descendants().filter(|t| t.is_text()).filter_map(|t| t.text()).collect::<String>())
+        .map(|t| t.split_whitespace().collect::<Vec<&str>>().join(" "))
+        .filter(|t| !t.is_empty())
+        .collect())
+}
+
+/// The labels of the entries in the navigation map of an NCX table of contents
+fn extract_ncx(buf: &[u8], path: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let ncx = read_file_from_zip(buf, path)?;
+    let doc = roxmltree::Document::parse(&ncx)?;
+    Ok(doc.descendants()
+        .filter(|n| n.has_tag_name("navLabel") && n.ancestors().any(|a| a.has_tag_name("navMap")))
+        .flat_map(|n| n.children().filter(|c| c.has_tag_name("text")))
+        .map(|n| n.descendants().filter(|t| t.is_text()).filter_map(|t| t.text()).collect::<String>())
+        .map(|t| String::from(t.trim()))
+        .filter(|t| !t.is_empty())
+        .collect())
+}
+
+impl Extract for Epub {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("epub") || (extension.is_none() && infer::book::is_epub(buf))
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let package_path = package_path(buf)?;
+        let package = read_file_from_zip(buf, &package_path)?;
+        let doc = roxmltree::Document::parse(&package)?;
+        let folder = package_path.rsplit_once('/').map(|(f, _)| f).unwrap_or("");
+        let section = |text: String, part: &str| Section { text, part: Some(String::from(part)), ..Default::default() };
+
+        let mut vec = Vec::new();
+        if self.metadata {
+            let metadata = doc.descendants().filter(|n| n.has_tag_name((OPF_NS, "metadata")));
+            for node in metadata.flat_map(|n| n.children()).filter(|n| METADATA_ELEMENTS.iter().any(|e| n.has_tag_name((DC_NS, *e)))) {
+                let text = node.descendants().filter(|t| t.is_text()).filter_map(|t| t.text()).collect::<String>();
+                let text = text.trim();
+                if !text.is_empty() {
+                    vec.push(section(String::from(text), "metadata"));
+                }
+            }
+        }
+
+        let items: Vec<Item> = doc.descendants()
+            .filter(|n| n.has_tag_name((OPF_NS, "item")))
+            .filter_map(|n| Some(Item {
+                id: n.attribute("id")?,
+                // Fragments are not part of the path of the file
+                path: resolve_path(folder, &percent_decode(n.attribute("href")?.split('#').next().unwrap_or(""))),
+                media_type: n.attribute("media-type").unwrap_or(""),
+                properties: n.attribute("properties").unwrap_or(""),
+            }))
+            .collect();
+        let navigation = items.iter().find(|i| i.properties.split_whitespace().any(|p| p == "nav"));
+
+        for itemref in doc.descendants().filter(|n| n.has_tag_name((OPF_NS, "itemref"))) {
+            let item = match items.iter().find(|i| Some(i.id) == itemref.attribute("idref")) {
+                Some(i) => i,
+                None => continue,
+            };
+            // The navigation document is extracted separately, also when it is part of the reading order
+            if item.media_type != "application/xhtml+xml" || navigation.map(|n| n.id) == Some(item.id) {
+                continue;
+            }
+            match extract_content_document(buf, &item.path) {
+                Ok(texts) => vec.extend(texts.into_iter().map(Section::from)),
+                Err(e) => vec.push(Section { warning: Some(format!("skipped {}: {}", item.path, e)), ..Default::default() }),
+            }
+        }
+
+        if self.navigation {
+            let ncx = doc.descendants()
+                .find(|n| n.has_tag_name((OPF_NS, "spine")))
+                .and_then(|n| n.attribute("toc"))
+                .and_then(|id| items.iter().find(|i| i.id == id));
+            let texts = match (navigation, ncx) {
+                (Some(n), _) => extract_nav(buf, &n.path)?,
+                (None, Some(n)) => extract_ncx(buf, &n.path)?,
+                (None, None) => Vec::new(),
+            };
+            vec.extend(texts.into_iter().map(|t| section(t, "navigation")));
+        }
+
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip_extensions::build_zip;
+
+    fn chapter(body: &str) -> String {
+        format!(r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml"><head><title>Not extracted</title></head><body>{}</body></html>"#, body)
+    }
+
+    #[test]
+    fn extracts_spine_in_reading_order() {
+        let container = r#"<?xml version="1.0"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles><rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/></rootfiles>
+</container>"#;
+        let package = r#"<?xml version="1.0"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="id">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="id">urn:uuid:1</dc:identifier>
+    <dc:title>The Book</dc:title>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="text/chapter1.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c2" href="text/chapter%202.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c3" href="text/missing.xhtml" media-type="application/xhtml+xml"/>
+    <item id="css" href="style.css" media-type="text/css"/>
+  </manifest>
+  <spine><itemref idref="nav"/><itemref idref="c2"/><itemref idref="c3"/><itemref idref="c1"/></spine>
+</package>"#;
+        let nav = chapter(r#"<nav xmlns:epub="http://www.idpf.org/2007/ops" epub:type="toc"><h1>Contents</h1><ol><li><a href="text/chapter%202.xhtml">Start</a></li><li><a href="text/chapter1.xhtml">End</a></li></ol></nav>
+<nav xmlns:epub="http://www.idpf.org/2007/ops" epub:type="landmarks"><ol><li><a epub:type="bodymatter" href="text/chapter%202.xhtml">Begin reading</a></li></ol></nav>"#);
+        let buf = build_zip(&[
+            ("mimetype", "application/epub+zip"),
+            ("META-INF/container.xml", container),
+            ("OEBPS/content.opf", package),
+            ("OEBPS/nav.xhtml", &nav),
+            ("OEBPS/text/chapter1.xhtml", &chapter("<p>The <em>last</em> chapter.</p>")),
+            ("OEBPS/text/chapter 2.xhtml", &chapter("<h1>Start</h1>\n<p>Text of <b>chapter</b> 2.</p>")),
+            ("OEBPS/style.css", "p { margin: 0 }"),
+        ]);
+
+        let sections = Epub::default().extract_sections(&buf).unwrap();
+        let sections: Vec<(&str, Option<&str>, bool)> = sections.iter().map(|s| (s.text.as_str(), s.part.as_deref(), s.warning.is_some())).collect();
+        assert_eq!(sections, vec![
+            ("The Book", Some("metadata"), false),
+            ("Start", None, false),
+            ("Text of chapter 2.", None, false),
+            ("", None, true),
+            ("The last chapter.", None, false),
+            ("Contents", Some("navigation"), false),
+            ("Start", Some("navigation"), false),
+            ("End", Some("navigation"), false),
+        ]);
+    }
+}
@@ -0,0 +1,231 @@
+use std::error::Error;
+use std::str::from_utf8;
+use crate::{extract::{Extract, Section}, icu::{Part, variants}};
+
+/// Extraction rule for Mozilla Fluent files (.ftl).
+/// The values and attributes of messages and terms are extracted, keyed by their id (and id.attribute).
+/// Select expressions are expanded into a variant for every branch, placeables such as { $name } are not counted.
+/// The comment right above a message is used as context.
+pub struct Fluent;
+
+struct PatternParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl PatternParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Whether the next line starts a new variant or closes the select expression
+    fn at_variant_end(&self) -> bool {
+        let rest: String = self.chars[self.pos..].iter().skip_while(|c| c.is_whitespace()).take(2).collect();
+        rest.starts_with('[') || rest.starts_with("*[") || rest.starts_with('}')
+    }
+
+    /// Parses text and placeables up to an unmatched closing brace, or up to the next variant inside a select expression
+    fn pattern(&mut self, in_variant: bool) -> Vec<Part> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '}' => break,
+                '{' => {
+                    self.pos += 1;
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                    parts.push(self.placeable());
+                },
+                '\n' if in_variant && self.at_variant_end() => break,
+                c => {
+                    text.push(c);
+                    self.pos += 1;
+                },
+            }
+        }
+        parts.push(Part::Text(text));
+        parts
+    }
+
+    /// Parses a placeable after its opening brace, up to and including its closing brace.
+    /// String literals are kept, variables, terms, message references and functions are left out.
+    fn placeable(&mut self) -> Part {
+        self.skip_whitespace();
+        let part = match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let mut literal = String::new();
+                while let Some(c) = self.peek() {
+                    self.pos += 1;
+                    match c {
+                        '"' => break,
+                        '\\' => {
+                            if let Some(c) = self.peek() {
+                                literal.push(c);
+                                self.pos += 1;
+                            }
+                        },
+                        c => literal.push(c),
+                    }
+                }
+                Part::Text(literal)
+            },
+            Some('{') => {
+                self.pos += 1;
+                self.placeable()
+            },
+            _ => {
+                let mut depth = 0;
+                while let Some(c) = self.peek() {
+                    match c {
+                        '(' => depth += 1,
+                        ')' => depth -= 1,
+                        '}' if depth == 0 => break,
+                        '-' if depth == 0 && self.chars.get(self.pos + 1) == Some(&'>') => {
+                            self.pos += 2;
+                            return self.select();
+                        },
+                        _ => (),
+                    }
+                    self.pos += 1;
+                }
+                Part::Text(String::new())
+            },
+        };
+        // Skip to the closing brace
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            if c == '}' {
+                break;
+            }
+        }
+        part
+    }
+
+    /// Parses the variants of a select expression after the arrow, up to and including its closing brace
+    fn select(&mut self) -> Part {
+        let mut branches = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('*') => self.pos += 1,
+                Some('[') => {
+                    while self.peek().is_some_and(|c| c != ']') {
+                        self.pos += 1;
+                    }
+                    self.pos += 1;
+                    branches.push(self.pattern(true));
+                },
+                Some('}') | None => break,
+                // Not valid Fluent, skip it
+                Some(_) => self.pos += 1,
+            }
+        }
+        self.pos += 1;
+        Part::Choice(branches)
+    }
+}
+
+/// Expands a pattern into its variants, trimming the whitespace around lines
+fn expand(pattern: &str) -> Vec<String> {
+    let mut parser = PatternParser { chars: pattern.chars().collect(), pos: 0 };
+    let mut parts = Vec::new();
+    while parser.pos < parser.chars.len() {
+        parts.append(&mut parser.pattern(false));
+        // Stray closing brace
+        parser.pos += 1;
+    }
+    let mut vec: Vec<String> = variants(&parts).iter()
+        .map(|v| v.lines().map(str::trim).filter(|l| !l.is_empty()).collect::<Vec<&str>>().join("\n"))
+        .collect();
+    vec.dedup();
+    vec
+}
+
+/// A message or term with its attributes, as the raw multiline patterns
+struct Entry {
+    id: String,
+    comment: Option<String>,
+    value: String,
+    attributes: Vec<(String, String)>,
+}
+
+fn is_identifier(s: &str) -> bool {
+    let s = s.strip_prefix('-').unwrap_or(s);
+    s.starts_with(|c: char| c.is_ascii_alphabetic()) && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+fn parse_entries(s: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+    // Whether indented lines continue the last entry
+    let mut in_entry = false;
+    for line in s.lines() {
+        if line.starts_with([' ', '\t']) || (line.is_empty() && in_entry) {
+            let entry = match entries.last_mut() {
+                Some(e) if in_entry => e,
+                _ => continue,
+            };
+            let trimmed = line.trim_start();
+            match trimmed.strip_prefix('.').and_then(|a| a.split_once('=')) {
+                Some((name, value)) if is_identifier(name.trim()) => {
+                    entry.attributes.push((String::from(name.trim()), String::from(value)));
+                },
+                _ => {
+                    let pattern = match entry.attributes.last_mut() {
+                        Some((_, value)) => value,
+                        None => &mut entry.value,
+                    };
+                    pattern.push('\n');
+                    pattern.push_str(line);
+                },
+            }
+            continue;
+        }
+
+        in_entry = false;
+        if let Some(comment) = line.strip_prefix('#') {
+            // Group (##) and resource (###) comments don't belong to the next message
+            if comment.starts_with('#') {
+                comments.clear();
+            } else {
+                comments.push(String::from(comment.trim()));
+            }
+        } else if let Some((id, value)) = line.split_once('=').filter(|(id, _)| is_identifier(id.trim())) {
+            let comment = if comments.is_empty() { None } else { Some(comments.join("\n")) };
+            entries.push(Entry { id: String::from(id.trim()), comment, value: String::from(value), attributes: Vec::new() });
+            comments.clear();
+            in_entry = true;
+        } else {
+            comments.clear();
+        }
+    }
+    entries
+}
+
+impl Extract for Fluent {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("ftl")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let s = from_utf8(buf)?;
+        let mut vec = Vec::new();
+        for entry in parse_entries(s) {
+            let patterns = std::iter::once((entry.id.clone(), &entry.value))
+                .chain(entry.attributes.iter().map(|(name, value)| (format!("{}.{}", entry.id, name), value)));
+            for (key, pattern) in patterns {
+                for text in expand(pattern).into_iter().filter(|t| !t.is_empty()) {
+                    vec.push(Section { text, key: Some(key.clone()), context: entry.comment.clone(), ..Default::default() });
+                }
+            }
+        }
+        Ok(vec)
+    }
+}
@@ -2,39 +2,267 @@ use std::error::Error;
 use crate::{extract::Extract};
 use std::str::from_utf8;
 
-use html_parser::{Dom, Node};
+use html_parser::{Dom, Node, Element};
 
+/// Extraction rule for HTML files and templates. Every block element (p, li, td, h1, ...) becomes a section, inline elements (b, a, span, ...) stay part of the sentence.
+/// Follows the W3C ITS 2.0 rules for HTML: content marked translate="no" (or the notranslate class) is skipped,
+/// and translatable attributes such as alt and title are extracted after the text of their block. Scripts and styles are never extracted.
 pub struct Html;
 
-fn extract_text_from_node(node: Node) -> Vec<String> {
-    match node {
-        Node::Text(t) => vec![t],
-        Node::Element(e) => {
-            let children_ref = &e.children;
-            if children_ref.into_iter().any(|n| n.text() != None) {
-                return vec![e.children.into_iter().map(|n| extract_text_from_node(n)).flatten().collect::<Vec<String>>().join(" ")];
+/// Elements whose content is not text
+const NON_TEXT_ELEMENTS: [&str; 2] = ["script", "style"];
+
+fn attribute<'a>(e: &'a Element, name: &str) -> Option<&'a str> {
+    e.attributes.get(name).and_then(|v| v.as_deref())
+}
+
+/// Whether the content and attributes of an element should be translated, given whether its parent's should be
+fn is_translatable(e: &Element, parent: bool) -> bool {
+    match attribute(e, "translate") {
+        Some("no") => false,
+        Some("yes") => true,
+        _ => parent && !e.classes.iter().any(|c| c == "notranslate"),
+    }
+}
+
+/// The values of the translatable attributes of an element, as defined by ITS 2.0 for HTML, and aria-label
+fn translatable_attributes(e: &Element) -> Vec<String> {
+    let name = e.name.to_lowercase();
+    let name = name.as_str();
+    let mut names = vec!["title", "aria-label"];
+    match name {
+        "th" => names.push("abbr"),
+        "area" | "img" => names.push("alt"),
+        "input" => {
+            names.extend(["alt", "placeholder"]);
+            if matches!(attribute(e, "type"), Some("button") | Some("submit") | Some("reset")) {
+                names.push("value");
             }
-            e.children.into_iter().map(|n| extract_text_from_node(n)).flatten().collect()
         },
-        Node::Comment(_) => Vec::new()
+        "textarea" => names.push("placeholder"),
+        "option" | "optgroup" | "menuitem" | "track" => names.push("label"),
+        "meta" if matches!(attribute(e, "name"), Some("description") | Some("keywords")) => names.push("content"),
+        _ => (),
+    }
+    names.into_iter().filter_map(|n| attribute(e, n)).filter(|v| !v.trim().is_empty()).map(String::from).collect()
+}
+
+/// Elements that stay inside the sentence of the surrounding text. All other elements start a new section.
+const INLINE_ELEMENTS: [&str; 33] = [
+    "a", "abbr", "b", "bdi", "bdo", "br", "button", "cite", "code", "data", "del", "dfn", "em", "font", "i", "img", "input",
+    "ins", "kbd", "label", "mark", "q", "s", "samp", "small", "span", "strong", "sub", "sup", "time", "u", "var", "wbr",
+];
+
+/// Collapses runs of HTML whitespace (space, tab, line feed, form feed and carriage return) into a single space
+fn collapse_whitespace(s: &str) -> String {
+    s.split([' ', '\t', '\n', '\u{c}', '\r']).filter(|w| !w.is_empty()).collect::<Vec<&str>>().join(" ")
+}
+
+/// The length of the tag, comment or declaration at the start of s, if it starts with one.
+/// A < that isn't followed by a letter, /, ! or ? is text.
+fn markup_len(s: &str) -> Option<usize> {
+    if !s.starts_with('<') || !s[1..].starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')) {
+        return None;
+    }
+    if s.starts_with("<!--") {
+        return Some(s.find("-->").map_or(s.len(), |i| i + 3));
+    }
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => (),
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '>' => return Some(i + 1),
+            None => (),
+        }
+    }
+    Some(s.len())
+}
+
+/// Finds a text node in the source of the document, outside of tags and comments and from the given position on.
+/// Returns where the text starts, with the whitespace between the position and the text. The parser trims this whitespace
+/// from the edges of text nodes and drops text nodes with only whitespace, e.g. the space in <b>bold</b> <i>italic</i>.
+fn locate(source: &str, mut position: usize, text: &str) -> Option<(usize, String)> {
+    let mut whitespace = String::new();
+    while position < source.len() {
+        let rest = &source[position..];
+        if rest.starts_with(text) {
+            return Some((position, whitespace));
+        }
+        if let Some(len) = markup_len(rest) {
+            position += len;
+            continue;
+        }
+        let c = rest.chars().next()?;
+        if matches!(c, ' ' | '\t' | '\n' | '\u{c}' | '\r') {
+            whitespace.push(c);
+        }
+        position += c.len_utf8();
+    }
+    None
+}
+
+/// Walks the DOM and builds a section for every block element, with the text of its inline elements included
+#[derive(Default)]
+struct Walker<'a> {
+    /// The source of the document, where the whitespace around text nodes is looked up
+    source: &'a str,
+    /// The position in the source after the last text node
+    position: usize,
+    sections: Vec<String>,
+    /// The text of the current block
+    current: String,
+    /// Whether the current block is inside a pre element, where whitespace is kept
+    preformatted: bool,
+    /// Translatable attributes of the current block, which are added after its text
+    attributes: Vec<String>,
+}
+
+impl<'a> Walker<'a> {
+    /// Ends the current block
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.current);
+        let text = if self.preformatted { String::from(text.trim_matches('\n')) } else { collapse_whitespace(&text) };
+        if !text.trim().is_empty() {
+            self.sections.push(text);
+        }
+        self.sections.append(&mut self.attributes);
+    }
+
+    /// Moves past a text node in the source and returns the whitespace before it
+    fn advance(&mut self, text: &str) -> String {
+        match locate(self.source, self.position, text) {
+            Some((start, whitespace)) => {
+                self.position = start + text.len();
+                whitespace
+            },
+            None => String::new(),
+        }
+    }
+
+    fn walk(&mut self, nodes: Vec<Node>, translate: bool, preformatted: bool) {
+        for node in nodes {
+            match node {
+                Node::Text(t) => {
+                    let whitespace = self.advance(&t);
+                    if translate {
+                        self.current.push_str(&whitespace);
+                        self.current.push_str(&t);
+                        self.preformatted = preformatted;
+                    }
+                },
+                Node::Element(e) => {
+                    let name = e.name.to_lowercase();
+                    if NON_TEXT_ELEMENTS.contains(&name.as_str()) {
+                        // Scripts can contain anything that looks like a tag
+                        for child in e.children {
+                            if let Node::Text(t) = child {
+                                self.advance(&t);
+                            }
+                        }
+                        continue;
+                    }
+                    let translate = is_translatable(&e, translate);
+                    if translate {
+                        self.attributes.append(&mut translatable_attributes(&e));
+                    }
+                    if INLINE_ELEMENTS.contains(&name.as_str()) {
+                        if name == "br" {
+                            self.current.push('\n');
+                        }
+                        self.walk(e.children, translate, preformatted);
+                    } else {
+                        self.flush();
+                        self.walk(e.children, translate, preformatted || name == "pre");
+                        self.flush();
+                    }
+                },
+                Node::Comment(_) => (),
+            }
+        }
     }
 }
 
+/// Takes the children of the body element out of the nodes, if there is one
+fn take_body(nodes: &mut [Node]) -> Option<Vec<Node>> {
+    for node in nodes.iter_mut() {
+        if let Node::Element(e) = node {
+            if e.name.eq_ignore_ascii_case("body") {
+                return Some(std::mem::take(&mut e.children));
+            }
+            if let Some(children) = take_body(&mut e.children) {
+                return Some(children);
+            }
+        }
+    }
+    None
+}
+
+/// Extracts the sections of the body of an (X)HTML document, leaving out the head. Used for the content documents of EPUB publications.
+pub(crate) fn extract_body(str: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut dom = Dom::parse(str)?;
+    let nodes = match take_body(&mut dom.children) {
+        Some(nodes) => nodes,
+        None => dom.children,
+    };
+    // Texts in the head are not walked, so they are looked up from the body on
+    let position = str.to_ascii_lowercase().find("<body").unwrap_or(0);
+    let mut walker = Walker { source: str, position, ..Default::default() };
+    walker.walk(nodes, true, false);
+    walker.flush();
+    Ok(walker.sections)
+}
 
 impl Extract for Html {
     fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
-        extension == Some("html") || extension == Some("htmlx") || infer::text::is_html(buf)
+        matches!(extension, Some("html") | Some("htm") | Some("htmlx") | Some("xhtml") | Some("shtml") | Some("php"))
+            // Only recognize HTML by its content when the extension is unknown, e.g. a Markdown file can start with an HTML comment
+            || (extension.is_none() && infer::text::is_html(buf))
     }
 
     fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
-        let str = from_utf8(buf)?;        
+        let str = from_utf8(buf)?;
         let dom = Dom::parse(str)?;
-        let mut vec = Vec::new();
-        
-        for child in dom.children {
-            vec.append(&mut extract_text_from_node(child))
-        }
+        let mut walker = Walker { source: str, ..Default::default() };
+        walker.walk(dom.children, true, false);
+        walker.flush();
+        Ok(walker.sections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        Ok(vec)
+    fn extract(html: &str) -> Vec<String> {
+        Html.extract(html.as_bytes()).unwrap()
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn keeps_spaces_around_inline_elements() {
+        assert_eq!(extract("<p>Click <b>here</b> now.</p>"), vec!["Click here now."]);
+        assert_eq!(extract("<p><b>Hi</b> there</p>"), vec!["Hi there"]);
+        assert_eq!(extract("<p><b>bold</b> <i>italic</i></p>"), vec!["bold italic"]);
+        assert_eq!(extract("<p>one<b>word</b></p>"), vec!["oneword"]);
+        assert_eq!(extract("<p>Hello <span translate=\"no\">Acme</span> world</p>"), vec!["Hello world"]);
+        assert_eq!(extract("<p><a href=\"#\" title=\"here\">here</a> and there</p>"), vec!["here and there", "here"]);
+    }
+
+    #[test]
+    fn keeps_preformatted_text() {
+        let html = "<html>\n<body>\n<ul>\n  <li>First</li>\n  <li>Second <a href=\"x>y\">link</a></li>\n</ul>\n<pre>\nlet a = 1;\n  let b = 2;\n</pre>\n<script>if (a <b) {}</script>\n</body>\n</html>\n";
+        assert_eq!(extract(html), vec!["First", "Second link", "let a = 1;\n  let b = 2;"]);
+    }
+
+    #[test]
+    fn keeps_private_use_characters() {
+        assert_eq!(extract("<p>\u{e000} Menu <i>\u{e001}</i>\u{e002}</p>"), vec!["\u{e000} Menu \u{e001}\u{e002}"]);
+    }
+
+    #[test]
+    fn keeps_spaces_in_body() {
+        let texts = extract_body("<html><head><title>Text</title></head><body><p>Text of <em>chapter</em> 1.</p></body></html>").unwrap();
+        assert_eq!(texts, vec!["Text of chapter 1."]);
+    }
+}
@@ -0,0 +1,125 @@
+use std::error::Error;
+use crate::{extract::{Extract, Section}, icu::expand};
+use super::json::{parse, Json};
+use serde_json::Value;
+
+/// Extraction rule for i18next translation files. Interpolations ({{name}}) and nested translations ($t(key)) are not counted.
+/// Plural variants (key_one, key_other, key_ordinal_few, ...) are extracted as separate sections, with their plural form as context.
+#[derive(Default)]
+pub struct I18next {
+    /// The JSON extraction rule used to walk the file, to filter keys with include and exclude patterns
+    pub json: Json,
+
+    /// The values are ICU MessageFormat messages (i18next-icu), plural and select branches are extracted as separate variants
+    pub icu: bool,
+}
+
+const PLURAL_FORMS: [&str; 6] = ["zero", "one", "two", "few", "many", "other"];
+
+/// The plural form of a key, e.g. "one" for item_one and "ordinal few" for place_ordinal_few
+fn plural_form(key: &str) -> Option<String> {
+    let (rest, form) = key.rsplit_once('_')?;
+    if form == "plural" {
+        return Some(String::from("plural"));
+    }
+    if !PLURAL_FORMS.contains(&form) {
+        return None;
+    }
+    if rest.ends_with("_ordinal") {
+        return Some(format!("ordinal {}", form));
+    }
+    Some(String::from(form))
+}
+
+/// Removes the parts of a value that are filled in at runtime: {{interpolations}} and $t(nested) translations
+fn strip_runtime_parts(s: &str) -> String {
+    let mut result = String::new();
+    let mut rest = s;
+    loop {
+        let next = [rest.find("{{"), rest.find("$t(")].into_iter().flatten().min();
+        let start = match next {
+            Some(i) => i,
+            None => break,
+        };
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = if rest.starts_with("{{") {
+            rest.find("}}").map(|i| i + 2)
+        } else {
+            let mut depth = 0;
+            rest.char_indices().find(|(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => (),
+                }
+                *c == ')' && depth == 0
+            }).map(|(i, _)| i + 1)
+        };
+        rest = &rest[end.unwrap_or(rest.len())..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Whether a parsed file uses i18next features: plural suffixes, interpolation or nesting
+fn looks_like_i18next(v: &Value) -> bool {
+    match v {
+        Value::String(s) => s.contains("{{") || s.contains("$t("),
+        Value::Array(values) => values.iter().any(looks_like_i18next),
+        Value::Object(map) => map.iter().any(|(k, v)| {
+            (v.is_string() && plural_form(k).is_some()) || looks_like_i18next(v)
+        }),
+        _ => false,
+    }
+}
+
+impl Extract for I18next {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("json") && parse(buf).is_ok_and(|v| looks_like_i18next(&v))
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let mut vec = Vec::new();
+        for section in self.json.extract_sections_from_value(&parse(buf)?) {
+            let form = section.key.as_deref().and_then(|k| k.rsplit('/').next()).and_then(plural_form);
+            let text = strip_runtime_parts(&section.text);
+            let texts = if self.icu { expand(&text) } else { vec![text] };
+            for text in texts {
+                vec.push(Section {
+                    text,
+                    context: form.as_ref().map(|f| format!("plural form: {}", f)),
+                    ..section.clone()
+                });
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_plural_forms_without_runtime_parts() {
+        let json = br#"{"greeting": "Hello {{name}}, $t(common.welcome) back", "items": {"file_one": "One file", "file_other": "{{count}} files"}, "place_ordinal_few": "{{count}}rd place"}"#;
+        assert!(I18next::default().can_extract(json, Some("json")));
+        assert!(!I18next::default().can_extract(br#"{"title": "Plain"}"#, Some("json")));
+        let sections = I18next::default().extract_sections(json).unwrap();
+        let sections: Vec<(&str, Option<&str>)> = sections.iter().map(|s| (s.text.as_str(), s.context.as_deref())).collect();
+        assert_eq!(sections, vec![
+            ("Hello ,  back", None),
+            ("One file", Some("plural form: one")),
+            (" files", Some("plural form: other")),
+            ("rd place", Some("plural form: ordinal few")),
+        ]);
+    }
+
+    #[test]
+    fn expands_icu_messages() {
+        let json = br#"{"files": "{count, plural, one {# file} other {# files}} in {{folder}}"}"#;
+        let i18next = I18next { icu: true, ..Default::default() };
+        assert_eq!(i18next.extract(json).unwrap(), vec![" file in ", " files in "]);
+    }
+}
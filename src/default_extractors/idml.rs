@@ -0,0 +1,330 @@
+use std::collections::HashSet;
+use std::error::Error;
+use roxmltree::Node;
+use crate::{extract::{Extract, Section}, zip_extensions::read_file_from_zip};
+
+/// Extraction rule for Adobe InDesign .idml packages. Stories are extracted in the order of the spreads in designmap.xml,
+/// with every paragraph (ended by a Br element), table cell and footnote as a section. Stories anchored in another story follow that story.
+/// Stories placed on master spreads follow the stories of the spreads, every story is extracted once.
+/// Stories that are only placed on hidden layers or on the pasteboard next to the pages are skipped by default,
+/// as is text with a hidden condition.
+pub struct Idml {
+    /// Extract stories whose frames are all on hidden layers
+    pub hidden_layers: bool,
+
+    /// Extract stories whose frames are all on the pasteboard, outside the pages of their spread
+    pub pasteboard: bool,
+
+    /// Extract the stories placed on master spreads, such as running headers
+    pub master_spreads: bool,
+}
+
+impl Default for Idml {
+    fn default() -> Self {
+        Idml {
+            hidden_layers: false,
+            pasteboard: false,
+            master_spreads: true,
+        }
+    }
+}
+
+/// Elements in stories that never contain text of the story itself
+const NON_TEXT_ELEMENTS: [&str; 4] = ["Properties", "Note", "HiddenText", "XMLAttribute"];
+
+/// Elements in stories whose text is a section of its own, without ending the surrounding paragraph
+const NESTED_ELEMENTS: [&str; 3] = ["Cell", "Footnote", "EndnoteRange"];
+
+/// An affine transformation (a b c d tx ty), as used in the ItemTransform attribute
+#[derive(Clone, Copy)]
+struct Transform([f64; 6]);
+
+impl Transform {
+    const IDENTITY: Transform = Transform([1.0, 0.0, 0.0, 1.0, 0.0, 0.0]);
+
+    fn parse(s: Option<&str>) -> Transform {
+        let values: Vec<f64> = s.unwrap_or("").split_whitespace().filter_map(|v| v.parse().ok()).collect();
+        match values.try_into() {
+            Ok(values) => Transform(values),
+            Err(_) => Transform::IDENTITY,
+        }
+    }
+
+    /// Applies this transformation after another one
+    fn after(&self, inner: &Transform) -> Transform {
+        let [a, b, c, d, tx, ty] = self.0;
+        let [ia, ib, ic, id, itx, ity] = inner.0;
+        Transform([
+            ia * a + ib * c,
+            ia * b + ib * d,
+            ic * a + id * c,
+            ic * b + id * d,
+            itx * a + ity * c + tx,
+            itx * b + ity * d + ty,
+        ])
+    }
+
+    fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let [a, b, c, d, tx, ty] = self.0;
+        (a * x + c * y + tx, b * x + d * y + ty)
+    }
+}
+
+/// An axis-aligned rectangle (left, top, right, bottom) in spread coordinates
+struct Bounds(f64, f64, f64, f64);
+
+impl Bounds {
+    fn from_points(points: &[(f64, f64)]) -> Option<Bounds> {
+        let (first, rest) = points.split_first()?;
+        Some(rest.iter().fold(Bounds(first.0, first.1, first.0, first.1), |b, (x, y)| {
+            Bounds(b.0.min(*x), b.1.min(*y), b.2.max(*x), b.3.max(*y))
+        }))
+    }
+
+    fn intersects(&self, other: &Bounds) -> bool {
+        self.0 < other.2 && other.0 < self.2 && self.1 < other.3 && other.1 < self.3
+    }
+}
+
+/// The transformation from the coordinates of a page item to the coordinates of its spread
+fn spread_transform(node: Node) -> Transform {
+    node.ancestors()
+        .take_while(|n| !n.has_tag_name("Spread") && !n.has_tag_name("MasterSpread"))
+        .filter(|n| n.is_element())
+        .fold(Transform::IDENTITY, |t, n| Transform::parse(n.attribute("ItemTransform")).after(&t))
+}
+
+/// The bounds of the pages of a spread
+fn page_bounds(spread: Node) -> Vec<Bounds> {
+    let mut vec = Vec::new();
+    for page in spread.descendants().filter(|n| n.has_tag_name("Page")) {
+        // GeometricBounds are top, left, bottom, right
+        let values: Vec<f64> = page.attribute("GeometricBounds").unwrap_or("").split_whitespace().filter_map(|v| v.parse().ok()).collect();
+        if let [top, left, bottom, right] = values[..] {
+            let transform = spread_transform(page);
+            let corners: Vec<(f64, f64)> = [(left, top), (right, top), (left, bottom), (right, bottom)].iter().map(|p| transform.apply(*p)).collect();
+            vec.extend(Bounds::from_points(&corners));
+        }
+    }
+    vec
+}
+
+/// The bounds of a frame, from the anchors of its path geometry
+fn frame_bounds(frame: Node) -> Option<Bounds> {
+    let transform = spread_transform(frame);
+    let points: Vec<(f64, f64)> = frame.descendants()
+        .filter(|n| n.has_tag_name("PathPointType"))
+        .filter_map(|n| {
+            let values: Vec<f64> = n.attribute("Anchor")?.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+            match values[..] {
+                [x, y] => Some(transform.apply((x, y))),
+                _ => None,
+            }
+        })
+        .collect();
+    Bounds::from_points(&points)
+}
+
+/// Walks a story and builds a section for every paragraph
+#[derive(Default)]
+struct Walker<'a> {
+    hidden_conditions: &'a [String],
+    sections: Vec<String>,
+    /// The text of the current paragraph
+    current: String,
+    /// Stories of frames anchored in the text, in order of appearance
+    anchored: Vec<String>,
+}
+
+impl<'a> Walker<'a> {
+    /// Ends the current paragraph
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.current);
+        let text = text.replace('\u{feff}', "").replace('\u{2028}', "\n");
+        let text = text.trim();
+        if !text.is_empty() {
+            self.sections.push(String::from(text));
+        }
+    }
+
+    fn walk(&mut self, node: Node) {
+        for child in node.children().filter(|n| n.is_element()) {
+            let name = child.tag_name().name();
+            // Also frames anchored in hidden text are hidden
+            let hidden = matches!(child.attribute("AppliedConditions"), Some(c) if c.split_whitespace().any(|c| self.hidden_conditions.iter().any(|h| h == c)));
+            if hidden || NON_TEXT_ELEMENTS.contains(&name) || (name == "Change" && child.attribute("ChangeType") == Some("DeletedText")) {
+                continue;
+            }
+            if let Some(story) = child.attribute("ParentStory") {
+                self.anchored.push(String::from(story));
+                continue;
+            }
+            match name {
+                "Content" => {
+                    // Processing instructions such as page numbers are left out
+                    for text in child.children().filter_map(|n| if n.is_text() { n.text() } else { None }) {
+                        self.current.push_str(text);
+                    }
+                },
+                "Br" => self.flush(),
+                name if NESTED_ELEMENTS.contains(&name) => {
+                    let current = std::mem::take(&mut self.current);
+                    self.walk(child);
+                    self.flush();
+                    self.current = current;
+                },
+                _ => self.walk(child),
+            }
+        }
+    }
+}
+
+impl Idml {
+    /// The IDs of the stories placed in the spreads, in spread order and followed by the master spreads, leaving out stories that are hidden or on the pasteboard
+    fn placed_stories(&self, buf: &[u8], designmap: &roxmltree::Document) -> Result<Vec<String>, Box<dyn Error>> {
+        let hidden_layers: Vec<&str> = designmap.root_element().children()
+            .filter(|n| n.has_tag_name("Layer") && n.attribute("Visible") == Some("false"))
+            .filter_map(|n| n.attribute("Self"))
+            .collect();
+        let spreads = designmap.root_element().children().filter(|n| n.has_tag_name("Spread"));
+        let master_spreads = designmap.root_element().children().filter(|n| self.master_spreads && n.has_tag_name("MasterSpread"));
+        let mut vec = Vec::new();
+        for src in spreads.chain(master_spreads).filter_map(|n| n.attribute("src")) {
+            let spread = read_file_from_zip(buf, src)?;
+            let doc = roxmltree::Document::parse(&spread)?;
+            let pages = page_bounds(doc.root_element());
+            for frame in doc.descendants().filter(|n| n.is_element()) {
+                let story = match frame.attribute("ParentStory") {
+                    Some(s) => String::from(s),
+                    None => continue,
+                };
+                let visible = frame.ancestors().all(|n| n.attribute("Visible") != Some("false"));
+                let on_visible_layer = self.hidden_layers || !matches!(frame.attribute("ItemLayer"), Some(l) if hidden_layers.contains(&l));
+                let on_page = self.pasteboard || match frame_bounds(frame) {
+                    Some(f) => pages.iter().any(|p| p.intersects(&f)),
+                    None => true,
+                };
+                if visible && on_visible_layer && on_page && !vec.contains(&story) {
+                    vec.push(story);
+                }
+            }
+        }
+        Ok(vec)
+    }
+}
+
+impl Extract for Idml {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("idml")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let designmap = read_file_from_zip(buf, "designmap.xml")?;
+        let designmap = roxmltree::Document::parse(&designmap)?;
+        let story_paths: Vec<&str> = designmap.root_element().children()
+            .filter(|n| n.has_tag_name("Story"))
+            .filter_map(|n| n.attribute("src"))
+            .collect();
+        let hidden_conditions: Vec<String> = designmap.root_element().children()
+            .filter(|n| n.has_tag_name("Condition") && n.attribute("Visible") == Some("false"))
+            .filter_map(|n| n.attribute("Self"))
+            .map(String::from)
+            .collect();
+
+        let mut vec = Vec::new();
+        let mut done = HashSet::new();
+        // Stories still to extract, anchored stories are put in front so they follow the story they are anchored in
+        let mut queue = self.placed_stories(buf, &designmap)?;
+        queue.reverse();
+        while let Some(id) = queue.pop() {
+            if !done.insert(id.clone()) {
+                continue;
+            }
+            let path = match story_paths.iter().find(|p| p.ends_with(&format!("Story_{}.xml", id))) {
+                Some(p) => p,
+                None => continue,
+            };
+            let story = read_file_from_zip(buf, path)?;
+            let doc = roxmltree::Document::parse(&story)?;
+            let mut walker = Walker { hidden_conditions: &hidden_conditions, ..Default::default() };
+            for node in doc.descendants().filter(|n| n.has_tag_name("Story") && n.attribute("Self").is_some()) {
+                walker.walk(node);
+                walker.flush();
+            }
+            vec.extend(walker.sections.into_iter().map(Section::from));
+            queue.extend(walker.anchored.into_iter().rev());
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip_extensions::build_zip;
+
+    const PACKAGING: &str = "http://ns.adobe.com/AdobeInDesign/idml/1.0/packaging";
+
+    /// A text frame with its corners at the given x positions, from the top to 100pt down
+    fn frame(story: &str, left: u32, right: u32, attributes: &str) -> String {
+        let points: String = [(left, 0), (left, 100), (right, 100), (right, 0)].iter()
+            .map(|(x, y)| format!(r#"<PathPointType Anchor="{0} {1}" LeftDirection="{0} {1}" RightDirection="{0} {1}"/>"#, x, y))
+            .collect();
+        format!(r#"<TextFrame Self="f{0}" ParentStory="{0}" ItemTransform="1 0 0 1 0 0" {1}><Properties><PathGeometry><GeometryPathType PathOpen="false">
+            <PathPointArray>{2}</PathPointArray></GeometryPathType></PathGeometry></Properties></TextFrame>"#, story, attributes, points)
+    }
+
+    fn spread(root: &str, frames: &[String]) -> String {
+        format!(r#"<idPkg:{0} xmlns:idPkg="{1}"><{0} Self="s{0}" ItemTransform="1 0 0 1 0 0">
+            <Page Self="p1" GeometricBounds="0 0 792 612" ItemTransform="1 0 0 1 0 0"/>{2}</{0}></idPkg:{0}>"#, root, PACKAGING, frames.concat())
+    }
+
+    fn story(id: &str, content: &str) -> String {
+        format!(r#"<idPkg:Story xmlns:idPkg="{}"><Story Self="{}"><ParagraphStyleRange>{}</ParagraphStyleRange></Story></idPkg:Story>"#, PACKAGING, id, content)
+    }
+
+    fn build_idml() -> Vec<u8> {
+        let designmap = format!(r#"<Document xmlns:idPkg="{}">
+            <Layer Self="visible" Name="Text" Visible="true"/><Layer Self="hidden" Name="Notes" Visible="false"/>
+            <Condition Self="Condition/Hidden" Name="Hidden" Visible="false"/>
+            <idPkg:MasterSpread src="MasterSpreads/MasterSpread_m.xml"/>
+            <idPkg:Spread src="Spreads/Spread_s.xml"/>
+            <idPkg:Story src="Stories/Story_main.xml"/><idPkg:Story src="Stories/Story_pasteboard.xml"/><idPkg:Story src="Stories/Story_layer.xml"/>
+            <idPkg:Story src="Stories/Story_anchored.xml"/><idPkg:Story src="Stories/Story_conditional.xml"/><idPkg:Story src="Stories/Story_master.xml"/>
+        </Document>"#, PACKAGING);
+        let main = story("main", r#"<CharacterStyleRange><Content>First paragraph.</Content><Br/>
+            <TextFrame Self="fanchored" ParentStory="anchored"/>
+            <TextFrame Self="fconditional" ParentStory="conditional" AppliedConditions="Condition/Hidden"/>
+            <Content>Second</Content></CharacterStyleRange>
+            <CharacterStyleRange AppliedConditions="Condition/Hidden"><Content> hidden</Content></CharacterStyleRange>
+            <CharacterStyleRange><Content> paragraph.</Content></CharacterStyleRange>"#);
+        build_zip(&[
+            ("designmap.xml", &designmap),
+            ("MasterSpreads/MasterSpread_m.xml", &spread("MasterSpread", &[frame("master", 10, 200, "")])),
+            ("Spreads/Spread_s.xml", &spread("Spread", &[
+                frame("pasteboard", 700, 800, ""),
+                frame("layer", 10, 200, r#"ItemLayer="hidden""#),
+                frame("main", 10, 200, r#"ItemLayer="visible""#),
+            ])),
+            ("Stories/Story_main.xml", &main),
+            ("Stories/Story_pasteboard.xml", &story("pasteboard", "<CharacterStyleRange><Content>On the pasteboard.</Content></CharacterStyleRange>")),
+            ("Stories/Story_layer.xml", &story("layer", "<CharacterStyleRange><Content>On a hidden layer.</Content></CharacterStyleRange>")),
+            ("Stories/Story_anchored.xml", &story("anchored", "<CharacterStyleRange><Content>Anchored.</Content></CharacterStyleRange>")),
+            ("Stories/Story_conditional.xml", &story("conditional", "<CharacterStyleRange><Content>Anchored in hidden text.</Content></CharacterStyleRange>")),
+            ("Stories/Story_master.xml", &story("master", "<CharacterStyleRange><Content>Running header</Content></CharacterStyleRange>")),
+        ])
+    }
+
+    #[test]
+    fn extracts_placed_stories_with_anchored_stories_after_their_parent() {
+        let buf = build_idml();
+        assert_eq!(Idml::default().extract(&buf).unwrap(), vec!["First paragraph.", "Second paragraph.", "Anchored.", "Running header"]);
+    }
+
+    #[test]
+    fn extracts_hidden_layers_and_pasteboard_when_asked() {
+        let buf = build_idml();
+        let idml = Idml { hidden_layers: true, pasteboard: true, master_spreads: false };
+        assert_eq!(idml.extract(&buf).unwrap(), vec!["On the pasteboard.", "On a hidden layer.", "First paragraph.", "Second paragraph.", "Anchored."]);
+    }
+}
@@ -1,47 +1,206 @@
 use std::error::Error;
-use crate::extract::Extract;
+use crate::extract::{Extract, Section};
 use serde_json::{Value};
+use std::str::from_utf8;
 
-pub struct Json;
+/// Extraction rule for .json, .json5 and .jsonc files. Every string value becomes a section keyed by its JSON pointer (e.g. /menu/items/0/label).
+/// Strings are extracted in the order they appear in the file and comments are skipped.
+///
+/// Strings can be filtered with include and exclude patterns. A pattern is either
+/// - a path glob on the JSON pointer, where `*` matches within a single key and `**` matches any number of keys, e.g. `/menu/**/label`
+/// - a JSONPath expression using child, wildcard, index and recursive descent selectors, e.g. `$.menu..label` or `$.items[*].title`
+/// - a glob on the key of the value only, when the pattern has no slashes, e.g. `icon` or `*_url`
+#[derive(Default)]
+pub struct Json {
+    /// Only extract strings that match one of these patterns. Everything is extracted when empty.
+    pub include: Vec<String>,
 
-fn extract_text_from_json_value(v: &Value) -> Vec<String> {
-    if v.is_string() {
-        match v.as_str() {
-            Some(w) => return vec![String::from(w)],
-            None => return Vec::new(),
+    /// Never extract strings that match one of these patterns
+    pub exclude: Vec<String>,
+
+    /// Also extract the keys of objects as translatable text
+    pub keys: bool,
+}
+
+/// Escapes a key for use in a JSON pointer (RFC 6901)
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Converts a JSONPath expression into the equivalent path glob
+fn json_path_to_glob(path: &str) -> String {
+    let mut glob = String::new();
+    let mut chars = path.trim_start_matches('$').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' if chars.peek() == Some(&'.') => {
+                chars.next();
+                glob += "/**/";
+            },
+            '.' => {
+                if !glob.ends_with('/') {
+                    glob.push('/');
+                }
+            },
+            '[' => {
+                let mut selector = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    selector.push(c);
+                }
+                if !glob.ends_with('/') {
+                    glob.push('/');
+                }
+                glob += &escape(selector.trim_matches(|c| c == '\'' || c == '"'));
+            },
+            c => glob.push(c),
         }
     }
+    if glob.is_empty() || glob.ends_with('/') {
+        glob.push_str("**");
+    }
+    glob
+}
 
-    if v.is_array() {
-        match v.as_array() {
-           Some(w) => return w.iter().flat_map(|x|extract_text_from_json_value(x)).collect(),
-           None => return Vec::new(),
+/// Glob match of a single key, where * matches any sequence of characters
+fn matches_key(pattern: &str, key: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == key,
+        Some((prefix, rest)) => {
+            let key = match key.strip_prefix(prefix) {
+                Some(k) => k,
+                None => return false,
+            };
+            (0..=key.len()).filter(|i| key.is_char_boundary(*i)).any(|i| matches_key(rest, &key[i..]))
         }
     }
+}
+
+/// Glob match of a path, where ** matches any number of keys
+fn matches_path(pattern: &[&str], path: &[String]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| matches_path(rest, &path[i..])),
+        Some((first, rest)) => match path.split_first() {
+            Some((key, path)) => matches_key(first, key) && matches_path(rest, path),
+            None => false,
+        },
+    }
+}
+
+/// Checks a JSON pointer, given as a list of escaped keys, against a pattern
+pub(crate) fn matches_pattern(pattern: &str, path: &[String]) -> bool {
+    if pattern.starts_with('$') {
+        return matches_pattern(&json_path_to_glob(pattern), path);
+    }
+    if !pattern.contains('/') {
+        return path.last().is_some_and(|key| matches_key(pattern, key));
+    }
+    let pattern: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    matches_path(&pattern, path)
+}
+
+impl Json {
+    fn is_wanted(&self, path: &[String]) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| matches_pattern(p, path)))
+            && !self.exclude.iter().any(|p| matches_pattern(p, path))
+    }
+
+    /// Extracts the wanted strings of a parsed document
+    pub(crate) fn extract_sections_from_value(&self, v: &Value) -> Vec<Section> {
+        let mut vec = Vec::new();
+        self.extract_text_from_json_value(v, &mut Vec::new(), &mut vec);
+        vec
+    }
 
-    if v.is_object() {
-        match v.as_object() {
-            Some(w) => {
-                let mut vec = Vec::new();
-                for (_, value) in w {
-                    vec.append(&mut extract_text_from_json_value(value))
+    fn extract_text_from_json_value(&self, v: &Value, path: &mut Vec<String>, vec: &mut Vec<Section>) {
+        match v {
+            Value::String(s) if self.is_wanted(path) => {
+                vec.push(Section { text: s.clone(), key: Some(pointer(path)), ..Default::default() });
+            },
+            Value::Array(values) => {
+                for (i, value) in values.iter().enumerate() {
+                    path.push(i.to_string());
+                    self.extract_text_from_json_value(value, path, vec);
+                    path.pop();
+                }
+            },
+            Value::Object(map) => {
+                for (key, value) in map {
+                    path.push(escape(key));
+                    if self.keys && self.is_wanted(path) {
+                        vec.push(Section { text: key.clone(), key: Some(pointer(path)), ..Default::default() });
+                    }
+                    self.extract_text_from_json_value(value, path, vec);
+                    path.pop();
                 }
-                return vec;
             },
-            None => return Vec::new()
+            _ => (),
         }
     }
+}
+
+fn pointer(path: &[String]) -> String {
+    path.iter().map(|key| format!("/{}", key)).collect()
+}
 
-    return Vec::new()
+/// Parses a JSON, JSON5 or JSONC file into a value that keeps the keys in document order.
+/// JSON5 is a superset of JSON with comments, so it also covers JSONC. Strict JSON is tried first as it parses faster.
+pub(crate) fn parse(buf: &[u8]) -> Result<Value, Box<dyn Error>> {
+    match serde_json::from_slice(buf) {
+        Ok(v) => Ok(v),
+        Err(_) => Ok(json5::from_str(from_utf8(buf)?)?),
+    }
 }
 
 impl Extract for Json {
     fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
-        extension == Some("json")
+        extension == Some("json") || extension == Some("json5") || extension == Some("jsonc")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        Ok(self.extract_sections_from_value(&parse(buf)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_with_include_and_exclude_patterns() {
+        let json = br#"{"a": [{"b": "One", "c": "Two"}, {"b": "Three"}], "d/e": {"f~g": "Four", "url": "x.html"}, "b": "Five"}"#;
+        let cases: [(&[&str], &[&str], &[&str]); 8] = [
+            (&[], &[], &["/a/0/b", "/a/0/c", "/a/1/b", "/d~1e/f~0g", "/d~1e/url", "/b"]),
+            (&["$.a[*].b"], &[], &["/a/0/b", "/a/1/b"]),
+            (&["/a/*/b"], &[], &["/a/0/b", "/a/1/b"]),
+            (&["$..b"], &[], &["/a/0/b", "/a/1/b", "/b"]),
+            (&["b"], &[], &["/a/0/b", "/a/1/b", "/b"]),
+            (&["$['d/e']['f~g']"], &[], &["/d~1e/f~0g"]),
+            (&["/d~1e/**"], &["url"], &["/d~1e/f~0g"]),
+            (&["/a/**"], &["$.a[1].*", "/a/**/c"], &["/a/0/b"]),
+        ];
+        for (include, exclude, keys) in cases {
+            let rule = Json {
+                include: include.iter().map(|p| p.to_string()).collect(),
+                exclude: exclude.iter().map(|p| p.to_string()).collect(),
+                ..Default::default()
+            };
+            let sections = rule.extract_sections(json).unwrap();
+            let found: Vec<&str> = sections.iter().filter_map(|s| s.key.as_deref()).collect();
+            assert_eq!(found, keys, "include {:?} exclude {:?}", include, exclude);
+        }
     }
 
-    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {       
-        let v: Value = serde_json::from_slice(buf)?;  
-        Ok(extract_text_from_json_value(&v))
+    #[test]
+    fn extracts_jsonc_in_document_order() {
+        let jsonc = b"// Settings\n{\n  \"zebra\": \"Last letter\", /* first in the file */\n  \"apple\": [\"First letter\", 1, true,],\n  \"mango\": {\"label\": \"Fruit\",},\n}\n";
+        let sections = Json::default().extract_sections(jsonc).unwrap();
+        let sections: Vec<(&str, Option<&str>)> = sections.iter().map(|s| (s.text.as_str(), s.key.as_deref())).collect();
+        assert_eq!(sections, vec![("Last letter", Some("/zebra")), ("First letter", Some("/apple/0")), ("Fruit", Some("/mango/label"))]);
+        let keys = Json { keys: true, ..Default::default() };
+        assert_eq!(keys.extract(jsonc).unwrap(), vec!["zebra", "Last letter", "apple", "First letter", "mango", "label", "Fruit"]);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,423 @@
+use std::error::Error;
+use crate::extract::Extract;
+use std::str::from_utf8;
+
+/// Extraction rule for LaTeX sources (.tex). Every paragraph, heading, caption, list item and table cell becomes a section,
+/// footnotes are separate sections that follow the section they are in.
+/// When there is a preamble, only the title and subtitle are extracted from it, besides the body of the document.
+/// Comments, math, verbatim blocks and the arguments of commands that are not text (\label, \ref, \cite, \includegraphics, ...) are skipped. Unknown commands are left out, but their arguments are kept as text.
+pub struct Latex;
+
+/// How the arguments of a command are handled, one character per mandatory argument:
+/// skipped (s), kept as part of the sentence (k), a section of its own (b) or a separate section without ending the sentence (n).
+/// Optional arguments in square brackets are always skipped.
+const COMMANDS: [(&str, &str); 74] = [
+    ("part", "b"), ("chapter", "b"), ("section", "b"), ("subsection", "b"), ("subsubsection", "b"), ("paragraph", "b"), ("subparagraph", "b"),
+    ("caption", "b"), ("title", "b"), ("subtitle", "b"), ("author", "s"), ("date", "s"),
+    ("footnote", "n"), ("footnotetext", "n"), ("marginpar", "n"), ("thanks", "n"),
+    ("label", "s"), ("ref", "s"), ("eqref", "s"), ("pageref", "s"), ("autoref", "s"), ("nameref", "s"), ("cref", "s"), ("Cref", "s"),
+    ("cite", "s"), ("citep", "s"), ("citet", "s"), ("citealp", "s"), ("citeauthor", "s"), ("citeyear", "s"), ("nocite", "s"), ("parencite", "s"),
+    ("textcite", "s"), ("footcite", "s"), ("bibitem", "s"), ("bibliography", "s"), ("bibliographystyle", "s"), ("addbibresource", "s"),
+    ("includegraphics", "s"), ("input", "s"), ("include", "s"), ("url", "s"), ("href", "sk"), ("hyperref", "k"),
+    ("textcolor", "sk"), ("colorbox", "sk"), ("color", "s"), ("multicolumn", "ssk"), ("multirow", "ssk"), ("ensuremath", "s"),
+    ("documentclass", "s"), ("usepackage", "s"), ("newcommand", "ss"), ("renewcommand", "ss"), ("providecommand", "ss"), ("def", "ss"),
+    ("newenvironment", "sss"), ("renewenvironment", "sss"), ("setlength", "ss"), ("setcounter", "ss"), ("addtocounter", "ss"),
+    ("vspace", "s"), ("hspace", "s"), ("fontsize", "ss"), ("pagestyle", "s"), ("thispagestyle", "s"), ("pagenumbering", "s"),
+    ("index", "s"), ("hypersetup", "s"), ("graphicspath", "s"), ("geometry", "s"), ("lstset", "s"), ("rule", "ss"), ("raisebox", "sk"),
+];
+
+/// Commands that start a new section
+const BREAKS: [&str; 6] = ["item", "bibitem", "par", "newpage", "clearpage", "maketitle"];
+
+/// Commands that stand for text
+const SYMBOLS: [(&str, &str); 13] = [
+    ("ldots", "…"), ("dots", "…"), ("textellipsis", "…"), ("LaTeX", "LaTeX"), ("TeX", "TeX"), ("textendash", "–"), ("textemdash", "—"),
+    ("ss", "ß"), ("S", "§"), ("P", "¶"), ("copyright", "©"), ("textregistered", "®"), ("texttrademark", "™"),
+];
+
+/// Environments whose content is not text
+const SKIPPED_ENVIRONMENTS: [&str; 31] = [
+    "equation", "equation*", "align", "align*", "alignat", "alignat*", "gather", "gather*", "multline", "multline*", "flalign", "flalign*",
+    "eqnarray", "eqnarray*", "math", "displaymath", "verbatim", "verbatim*", "Verbatim", "BVerbatim", "lstlisting", "minted", "comment",
+    "alltt", "tikzpicture", "pgfpicture", "filecontents", "filecontents*", "thebibliography", "CJK*", "dmath",
+];
+
+/// Number of arguments of environments that are not text, e.g. the column specification of tabular
+const ENVIRONMENT_ARGUMENTS: [(&str, usize); 9] = [
+    ("tabular", 1), ("tabular*", 2), ("tabularx", 2), ("tabulary", 2), ("longtable", 1), ("minipage", 1), ("multicols", 1), ("wrapfigure", 2), ("subfigure", 1),
+];
+
+/// Environments in which & and \\ separate table cells
+const TABLE_ENVIRONMENTS: [&str; 5] = ["tabular", "tabular*", "tabularx", "tabulary", "longtable"];
+
+/// Accent commands and the combining characters they add to the next letter
+const ACCENTS: [(char, char); 9] = [
+    ('\'', '\u{301}'), ('`', '\u{300}'), ('^', '\u{302}'), ('"', '\u{308}'), ('~', '\u{303}'), ('=', '\u{304}'), ('.', '\u{307}'), ('c', '\u{327}'), ('v', '\u{30c}'),
+];
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    sections: Vec<String>,
+    /// The text of the current section
+    current: String,
+    /// Footnotes and other notes in the current section, which are added after its text
+    notes: Vec<String>,
+    /// The open environments
+    environments: Vec<String>,
+    /// Whether the parser is before \begin{document}, where only the arguments of sectioning commands such as \title are kept
+    preamble: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    /// Ends the current section
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.current);
+        let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !text.is_empty() && !self.preamble {
+            self.sections.push(text);
+        }
+        self.sections.append(&mut self.notes);
+    }
+
+    fn in_table(&self) -> bool {
+        self.environments.last().map(|e| TABLE_ENVIRONMENTS.contains(&e.as_str())) == Some(true)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().map(char::is_whitespace) == Some(true) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips to the end of the line, together with the line feed and the leading whitespace of the next line, like TeX does.
+    /// E.g. "foo%" followed by a line with "bar" is "foobar".
+    fn skip_comment(&mut self) {
+        while !matches!(self.peek(), Some('\n') | None) {
+            self.pos += 1;
+        }
+        self.pos = (self.pos + 1).min(self.chars.len());
+        while matches!(self.peek(), Some(' ') | Some('\t') | Some('\r')) {
+            self.pos += 1;
+        }
+        // The next line is empty, which ends the paragraph
+        if self.peek() == Some('\n') {
+            self.flush();
+        }
+    }
+
+    /// Skips past the next occurrence of the end string that is not escaped, e.g. the end of math or a verbatim environment
+    fn skip_past(&mut self, end: &str) {
+        while self.pos < self.chars.len() && !self.starts_with(end) {
+            self.pos += if self.peek() == Some('\\') && !end.starts_with('\\') { 2 } else { 1 };
+        }
+        self.pos = (self.pos + end.chars().count()).min(self.chars.len());
+    }
+
+    /// Skips a group with balanced braces, the opening brace being the current character
+    fn skip_group(&mut self) {
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '\\' => self.pos += 1,
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return;
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+
+    /// Skips optional arguments in square brackets and the star of starred commands
+    fn skip_optional(&mut self) {
+        loop {
+            let start = self.pos;
+            self.skip_whitespace();
+            match self.peek() {
+                Some('*') => self.pos += 1,
+                Some('[') => {
+                    let mut depth = 0;
+                    while let Some(c) = self.peek() {
+                        self.pos += 1;
+                        match c {
+                            '{' => depth += 1,
+                            '}' => depth -= 1,
+                            ']' if depth == 0 => break,
+                            _ => (),
+                        }
+                    }
+                },
+                _ => {
+                    self.pos = start;
+                    return;
+                },
+            }
+        }
+    }
+
+    /// Skips a mandatory argument: a group, a command or a single character
+    fn skip_argument(&mut self) {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('{') => self.skip_group(),
+            Some('\\') => {
+                self.pos += 1;
+                self.read_name();
+            },
+            Some(_) => self.pos += 1,
+            None => (),
+        }
+    }
+
+    /// Reads a mandatory argument without interpreting it, e.g. the name of an environment
+    fn read_argument(&mut self) -> String {
+        self.skip_whitespace();
+        let start = self.pos;
+        self.skip_argument();
+        let end = self.pos;
+        let braced = self.chars.get(start) == Some(&'{') && end > start + 1;
+        let range = if braced { start + 1..end - 1 } else { start..end };
+        self.chars[range].iter().collect::<String>().trim().to_string()
+    }
+
+    /// Reads the name of a command after the backslash: a run of letters or a single other character
+    fn read_name(&mut self) -> String {
+        let start = self.pos;
+        while self.peek().map(|c| c.is_ascii_alphabetic()) == Some(true) {
+            self.pos += 1;
+        }
+        if self.pos == start && self.pos < self.chars.len() {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    /// Parses a mandatory argument as text of the current section
+    fn argument(&mut self) {
+        self.skip_whitespace();
+        if self.peek() == Some('{') {
+            self.pos += 1;
+            self.text(true);
+        } else {
+            self.skip_argument();
+        }
+    }
+
+    /// Parses text until the end of the document, or until the closing brace of the current group
+    fn text(&mut self, group: bool) {
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '}' if group => return,
+                '{' => self.text(true),
+                '}' => (),
+                '%' => self.skip_comment(),
+                '$' if self.peek() == Some('$') => {
+                    self.pos += 1;
+                    self.skip_past("$$");
+                },
+                '$' => self.skip_past("$"),
+                '~' => self.current.push(' '),
+                '&' if self.in_table() => self.flush(),
+                '\n' => {
+                    // An empty line ends the paragraph
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(' ') | Some('\t') | Some('\r')) {
+                        self.pos += 1;
+                    }
+                    if self.peek() == Some('\n') {
+                        self.flush();
+                    } else {
+                        self.current.push(' ');
+                        self.pos = start;
+                    }
+                },
+                '\\' => self.command(),
+                c => self.current.push(c),
+            }
+        }
+    }
+
+    fn command(&mut self) {
+        let name = self.read_name();
+        let name = name.as_str();
+        match name {
+            "" => (),
+            "\\" => {
+                self.skip_optional();
+                if self.in_table() { self.flush() } else { self.current.push(' ') }
+            },
+            "%" | "&" | "$" | "#" | "_" | "{" | "}" => self.current.push_str(name),
+            " " | "," | ";" | ":" | "!" | "\n" | "quad" | "qquad" | "newline" | "linebreak" => self.current.push(' '),
+            "(" => self.skip_past("\\)"),
+            "[" => self.skip_past("\\]"),
+            "verb" | "lstinline" | "mintinline" => {
+                self.skip_optional();
+                if name == "mintinline" {
+                    self.skip_argument();
+                }
+                match self.peek() {
+                    Some('{') => self.skip_group(),
+                    Some(delimiter) => {
+                        // A backslash is not an escape in verbatim text, e.g. \verb|C:\|
+                        self.pos += 1;
+                        while self.peek().is_some_and(|c| c != delimiter) {
+                            self.pos += 1;
+                        }
+                        self.pos = (self.pos + 1).min(self.chars.len());
+                    },
+                    None => (),
+                }
+            },
+            "begin" => {
+                let environment = self.read_argument();
+                if SKIPPED_ENVIRONMENTS.contains(&environment.as_str()) {
+                    self.skip_past(&format!("\\end{{{}}}", environment));
+                    return;
+                }
+                self.flush();
+                if environment == "document" {
+                    self.preamble = false;
+                }
+                let arguments = ENVIRONMENT_ARGUMENTS.iter().find(|(e, _)| *e == environment).map(|(_, n)| *n).unwrap_or(0);
+                for _ in 0..arguments {
+                    self.skip_optional();
+                    self.skip_argument();
+                }
+                self.skip_optional();
+                self.environments.push(environment);
+            },
+            "end" => {
+                let environment = self.read_argument();
+                self.flush();
+                if self.environments.last() == Some(&environment) {
+                    self.environments.pop();
+                }
+                if environment == "document" {
+                    self.pos = self.chars.len();
+                }
+            },
+            _ => {
+                if let Some((_, text)) = SYMBOLS.iter().find(|(s, _)| *s == name) {
+                    self.current.push_str(text);
+                    return;
+                }
+                if let Some((_, mark)) = ACCENTS.iter().find(|(a, _)| name.len() == 1 && name.starts_with(*a)) {
+                    let letter = self.read_argument();
+                    self.current.push_str(&letter);
+                    self.current.push(*mark);
+                    return;
+                }
+                if BREAKS.contains(&name) {
+                    self.flush();
+                }
+                let arguments = COMMANDS.iter().find(|(c, _)| *c == name).map(|(_, a)| *a).unwrap_or("");
+                for argument in arguments.chars() {
+                    self.skip_optional();
+                    match argument {
+                        'k' => self.argument(),
+                        'b' => {
+                            self.flush();
+                            // E.g. the title, which is set in the preamble
+                            let preamble = std::mem::replace(&mut self.preamble, false);
+                            self.argument();
+                            self.flush();
+                            self.preamble = preamble;
+                        },
+                        'n' => {
+                            let current = std::mem::take(&mut self.current);
+                            let notes = std::mem::take(&mut self.notes);
+                            let sections = std::mem::take(&mut self.sections);
+                            self.argument();
+                            self.flush();
+                            let note = std::mem::replace(&mut self.sections, sections);
+                            self.current = current;
+                            self.notes = notes;
+                            self.notes.extend(note);
+                        },
+                        _ => self.skip_argument(),
+                    }
+                }
+                // Arguments of unknown commands are parsed as text
+                self.skip_optional();
+            },
+        }
+    }
+}
+
+/// Whether the document has a preamble, i.e. \begin{document} appears outside of a comment
+fn has_preamble(str: &str) -> bool {
+    str.lines().any(|line| {
+        let comment = line.char_indices().find(|(i, c)| *c == '%' && !line[..*i].ends_with('\\')).map(|(i, _)| i);
+        line[..comment.unwrap_or(line.len())].contains("\\begin{document}")
+    })
+}
+
+impl Extract for Latex {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("tex") || extension == Some("ltx")
+    }
+
+    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        let str = from_utf8(buf)?;
+        // Apart from the title, the preamble before \begin{document} only contains settings and definitions
+        let preamble = has_preamble(str);
+        let mut parser = Parser {
+            chars: str.chars().collect(),
+            pos: 0,
+            sections: Vec::new(),
+            current: String::new(),
+            notes: Vec::new(),
+            environments: if preamble { Vec::new() } else { vec![String::from("document")] },
+            preamble,
+        };
+        parser.text(false);
+        parser.flush();
+        Ok(parser.sections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(tex: &str) -> Vec<String> {
+        Latex.extract(tex.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn keeps_title_from_preamble() {
+        let tex = "\\documentclass{article}\n% \\begin{document} in a comment\n\\usepackage[utf8]{inputenc}\n\\setmainfont{Arial}\n\\title{A Study\\thanks{Funded by nobody.}}\n\\author{Someone}\n\\begin{document}\n\\maketitle\n\\section{Intro}\nSome text.\n\\end{document}\n";
+        assert_eq!(extract(tex), vec!["A Study", "Funded by nobody.", "Intro", "Some text."]);
+    }
+
+    #[test]
+    fn notes_follow_their_section() {
+        let tex = "First\\footnote{One.} and\\marginpar{Two \\footnote{Three.} notes.} more.\n\nNext.\n";
+        assert_eq!(extract(tex), vec!["First and more.", "One.", "Two notes.", "Three.", "Next."]);
+    }
+
+    #[test]
+    fn backslash_ends_verbatim_text() {
+        assert_eq!(extract("Open \\verb|C:\\| or \\verb+D:\\+ now.\n"), vec!["Open or now."]);
+    }
+
+    #[test]
+    fn comments_join_lines() {
+        assert_eq!(extract("foo%\n   bar and 50\\% more\n"), vec!["foobar and 50% more"]);
+        assert_eq!(extract("First% comment\n\nSecond\n"), vec!["First", "Second"]);
+    }
+}
@@ -0,0 +1,211 @@
+use std::error::Error;
+use crate::extract::Extract;
+use super::html::Html;
+use std::str::from_utf8;
+use pulldown_cmark::{Event, Options, Parser, Tag};
+
+/// Extraction rule for Markdown files. Every heading, paragraph, list item and table cell becomes its own section.
+/// Code, html tags and link destinations are skipped, only link texts, image alt texts and the text of html blocks are extracted.
+/// Alt texts follow the section of the image.
+pub struct Markdown {
+    /// The top level fields of the front matter (at the start of the file between `---` lines) that contain translatable text
+    pub front_matter_fields: Vec<String>,
+}
+
+impl Default for Markdown {
+    fn default() -> Self {
+        Markdown {
+            front_matter_fields: vec![String::from("title"), String::from("description")]
+        }
+    }
+}
+
+/// Splits a file into its front matter and the markdown body
+fn split_front_matter(s: &str) -> (Option<&str>, &str) {
+    for fence in ["---", "+++"] {
+        let rest = match s.strip_prefix(fence).and_then(|r| r.strip_prefix("\r\n").or_else(|| r.strip_prefix('\n'))) {
+            Some(r) => r,
+            None => continue,
+        };
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            if line.trim_end() == fence {
+                return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+            }
+            offset += line.len();
+        }
+    }
+    (None, s)
+}
+
+fn unquote(s: &str) -> String {
+    let s = s.trim();
+    if s.len() >= 2 && ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\''))) {
+        return s[1..s.len() - 1].replace("\\\"", "\"").replace("''", "'");
+    }
+    String::from(s)
+}
+
+/// Reads the requested top level fields from YAML (`key: value`) or TOML (`key = "value"`) front matter.
+/// Block scalars (`key: |` and `key: >`) are joined into a single section.
+fn extract_front_matter(front_matter: &str, fields: &[String]) -> Vec<String> {
+    let mut vec = Vec::new();
+    let mut block: Option<String> = None;
+    for line in front_matter.lines() {
+        if let Some(b) = block.as_mut() {
+            if line.starts_with([' ', '\t']) || line.trim().is_empty() {
+                b.push(' ');
+                b.push_str(line.trim());
+                continue;
+            }
+            vec.push(b.trim().to_string());
+            block = None;
+        }
+        if line.starts_with([' ', '\t', '#']) {
+            continue;
+        }
+        let (key, value) = match line.find([':', '=']) {
+            Some(i) => (line[..i].trim(), line[i + 1..].trim()),
+            None => continue,
+        };
+        if !fields.iter().any(|f| f == key) {
+            continue;
+        }
+        if value.starts_with('|') || value.starts_with('>') {
+            block = Some(String::new());
+        } else if !value.is_empty() {
+            vec.push(unquote(value));
+        }
+    }
+    if let Some(b) = block {
+        vec.push(b.trim().to_string());
+    }
+    vec
+}
+
+/// Extracts the text of raw html with the [HTML](super::html::Html) rule. Inline tags such as <b> have no text of their own,
+/// the text between them is part of the markdown. When the html can't be parsed, the tags are stripped.
+fn extract_html(html: &str) -> Vec<String> {
+    if let Ok(texts) = Html.extract(html.as_bytes()) {
+        return texts;
+    }
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            },
+            c if !in_tag => text.push(c),
+            _ => (),
+        }
+    }
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if text.is_empty() { Vec::new() } else { vec![text] }
+}
+
+fn extract_text_from_markdown(s: &str) -> Vec<String> {
+    let mut vec = Vec::new();
+    let mut current = String::new();
+    let mut html = String::new();
+    let mut alt_texts: Vec<String> = Vec::new();
+    // Alt texts of the images in the current section, which are added after its text
+    let mut pending_alt_texts: Vec<String> = Vec::new();
+    let mut code_depth = 0;
+
+    let flush = |vec: &mut Vec<String>, current: &mut String, pending_alt_texts: &mut Vec<String>| {
+        let text = current.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !text.is_empty() {
+            vec.push(text);
+        }
+        current.clear();
+        vec.extend(pending_alt_texts.drain(..).filter(|a| !a.trim().is_empty()));
+    };
+
+    let parser = Parser::new_ext(s, Options::ENABLE_TABLES | Options::ENABLE_FOOTNOTES | Options::ENABLE_STRIKETHROUGH);
+    for event in parser {
+        if let Event::Html(h) = event {
+            html.push_str(&h);
+            continue;
+        }
+        // Html blocks are split into an event per line, including the line feed. Inline html is an event per tag.
+        if html.ends_with('\n') {
+            flush(&mut vec, &mut current, &mut pending_alt_texts);
+            vec.extend(extract_html(&html));
+        }
+        html.clear();
+        match event {
+            Event::Start(Tag::CodeBlock(_)) => code_depth += 1,
+            Event::End(Tag::CodeBlock(_)) => code_depth -= 1,
+            Event::Start(Tag::Image(..)) => alt_texts.push(String::new()),
+            Event::End(Tag::Image(..)) => {
+                if let Some(alt) = alt_texts.pop() {
+                    pending_alt_texts.push(alt);
+                }
+            },
+            Event::Start(Tag::Paragraph | Tag::Heading(..) | Tag::Item | Tag::TableCell | Tag::FootnoteDefinition(_))
+            | Event::End(Tag::Paragraph | Tag::Heading(..) | Tag::Item | Tag::TableCell | Tag::FootnoteDefinition(_)) => {
+                flush(&mut vec, &mut current, &mut pending_alt_texts);
+            },
+            Event::Text(t) if code_depth == 0 => match alt_texts.last_mut() {
+                Some(alt) => alt.push_str(&t),
+                None => current.push_str(&t),
+            },
+            Event::SoftBreak | Event::HardBreak => current.push(' '),
+            _ => (),
+        }
+    }
+    if !html.is_empty() {
+        flush(&mut vec, &mut current, &mut pending_alt_texts);
+        vec.extend(extract_html(&html));
+    }
+    flush(&mut vec, &mut current, &mut pending_alt_texts);
+    vec
+}
+
+impl Extract for Markdown {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("md") || extension == Some("markdown")
+    }
+
+    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        let s = from_utf8(buf)?.trim_start_matches('\u{feff}');
+        let (front_matter, body) = split_front_matter(s);
+        let mut vec = match front_matter {
+            Some(f) => extract_front_matter(f, &self.front_matter_fields),
+            None => Vec::new(),
+        };
+        vec.append(&mut extract_text_from_markdown(body));
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(md: &str) -> Vec<String> {
+        Markdown::default().extract(md.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn extracts_text_of_html() {
+        assert_eq!(extract("<div>Hello world</div>\n\nAfter."), vec!["Hello world", "After."]);
+        assert_eq!(extract("<table>\n<tr><td>One</td><td>Two</td></tr>\n</table>\n"), vec!["One", "Two"]);
+        assert_eq!(extract("Click <b>here</b> now."), vec!["Click here now."]);
+        assert_eq!(extract("<!-- note -->\n# Title"), vec!["Title"]);
+    }
+
+    #[test]
+    fn alt_texts_follow_their_paragraph() {
+        assert_eq!(extract("Intro ![alt](x.png) outro\n\nNext."), vec!["Intro outro", "alt", "Next."]);
+    }
+
+    #[test]
+    fn skips_code_and_link_destinations() {
+        let md = "---\ntitle: Guide\nlayout: post\n---\n# Install\n\nRun `cargo` or see [the docs](https://example.com).\n\n```\nfn main() {}\n```\n";
+        assert_eq!(extract(md), vec!["Guide", "Install", "Run or see the docs."]);
+    }
+}
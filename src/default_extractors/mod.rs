@@ -5,4 +5,24 @@ pub mod json;
 pub mod pptx;
 pub mod xlsx;
 pub mod xliff;
-pub mod html;
\ No newline at end of file
+pub mod html;
+pub mod pdf;
+pub mod odf;
+pub mod markdown;
+pub mod i18next;
+pub mod chrome;
+pub mod arb;
+pub mod yaml;
+pub mod android;
+pub mod apple;
+pub mod resx;
+pub mod properties;
+pub mod qt;
+pub mod fluent;
+pub mod sdlxliff;
+pub mod mqxliff;
+pub mod dita;
+pub mod docbook;
+pub mod idml;
+pub mod epub;
+pub mod latex;
\ No newline at end of file
@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::str::from_utf8;
+use roxmltree::Node;
+use crate::{extract::{Extract, Section}, progress::{Bilingual, TranslationState, Unit}};
+use super::xliff::{units_1_2, pending_sections};
+
+/// Extraction rule for memoQ .mqxliff files. Only the source text of unconfirmed, unlocked segments is extracted.
+/// The status, match rate and lock of every trans-unit (mq:status, mq:percent, mq:locked) are used for the [progress](crate::progress::Progress).
+/// Pretranslated segments that were not confirmed yet count as needing translation, with the pretranslation as origin.
+pub struct Mqxliff;
+
+const MQ_NS: &str = "MQXliff";
+
+/// Updates a unit with the memoQ attributes of its trans-unit
+fn read_status(trans_unit: Node, _mid: Option<&str>, unit: &mut Unit) {
+    let attribute = |name: &str| trans_unit.attribute((MQ_NS, name));
+    let (state, origin) = match attribute("status") {
+        Some("NotStarted") => (TranslationState::New, None),
+        Some("ManuallyConfirmed") => (TranslationState::Translated, None),
+        Some("Reviewer1Confirmed") => (TranslationState::Reviewed, None),
+        Some("Reviewer2Confirmed") => (TranslationState::Final, None),
+        Some("Pretranslated") => (TranslationState::NeedsTranslation, Some("tm")),
+        Some("MachineTranslated") => (TranslationState::NeedsTranslation, Some("mt")),
+        Some("AssembledFromFragments") => (TranslationState::NeedsTranslation, Some("fragments")),
+        // PartiallyEdited, Edited and Rejected
+        Some(_) => (TranslationState::NeedsTranslation, None),
+        None => return,
+    };
+    unit.state = state;
+    unit.origin = origin.map(String::from).or(unit.origin.take());
+    unit.locked = attribute("locked") == Some("Locked");
+    unit.match_percent = attribute("percent").and_then(|p| p.parse().ok()).filter(|p| *p > 0);
+}
+
+impl Bilingual for Mqxliff {
+    fn can_read(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("mqxliff")
+    }
+
+    fn units(&self, buf: &[u8]) -> Result<Vec<Unit>, Box<dyn Error>> {
+        let str = from_utf8(buf)?;
+        let doc = roxmltree::Document::parse(str)?;
+        Ok(units_1_2(&doc, read_status))
+    }
+}
+
+impl Extract for Mqxliff {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        self.can_read(buf, extension)
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        Ok(pending_sections(self.units(buf)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_statuses_origins_and_locks() {
+        let mqxliff = br#"<xliff version="1.2" xmlns:mq="MQXliff"><file><body>
+            <trans-unit id="1" mq:status="ManuallyConfirmed" mq:percent="0"><source>Confirmed</source><target>Confirme</target></trans-unit>
+            <trans-unit id="2" mq:status="Pretranslated" mq:percent="95"><source>Fuzzy</source><target>Flou</target></trans-unit>
+            <trans-unit id="3" mq:status="NotStarted" mq:locked="Locked"><source>Locked</source></trans-unit>
+            <trans-unit id="4" mq:status="Reviewer2Confirmed"><source>Final</source><target>Final</target></trans-unit>
+        </body></file></xliff>"#;
+        let units = Mqxliff.units(mqxliff).unwrap();
+        let units: Vec<(&str, TranslationState, Option<&str>, Option<u32>, bool)> = units.iter()
+            .map(|u| (u.source.as_str(), u.state, u.origin.as_deref(), u.match_percent, u.locked))
+            .collect();
+        assert_eq!(units, vec![
+            ("Confirmed", TranslationState::Translated, None, None, false),
+            ("Fuzzy", TranslationState::NeedsTranslation, Some("tm"), Some(95), false),
+            ("Locked", TranslationState::New, None, None, true),
+            ("Final", TranslationState::Final, None, None, false),
+        ]);
+        assert_eq!(Mqxliff.extract(mqxliff).unwrap(), vec!["Fuzzy"]);
+    }
+}
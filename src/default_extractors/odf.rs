@@ -0,0 +1,241 @@
+use std::collections::HashSet;
+use std::error::Error;
+use crate::{extract::Extract, zip_extensions::read_file_from_zip};
+use roxmltree::{Document, Node};
+
+const TEXT_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:text:1.0";
+const STYLE_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:style:1.0";
+const FO_NS: &str = "urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0";
+
+/// Extraction rule for OpenDocument text (.odt) files
+pub struct Odt {
+    /// Extract the headers and footers defined on the master pages in styles.xml
+    pub headers_footers: bool,
+
+    /// Extract hidden paragraphs and hidden text
+    pub hidden: bool,
+}
+
+/// Extraction rule for OpenDocument spreadsheet (.ods) files
+pub struct Ods {
+    /// Extract the headers and footers defined on the master pages in styles.xml
+    pub headers_footers: bool,
+
+    /// Extract hidden paragraphs and hidden text
+    pub hidden: bool,
+}
+
+/// Extraction rule for OpenDocument presentation (.odp) files
+pub struct Odp {
+    /// Extract the speaker notes of each slide
+    pub notes: bool,
+
+    /// Extract hidden paragraphs and hidden text
+    pub hidden: bool,
+}
+
+impl Default for Odt {
+    fn default() -> Self {
+        Odt { headers_footers: true, hidden: false }
+    }
+}
+
+impl Default for Ods {
+    fn default() -> Self {
+        Ods { headers_footers: true, hidden: false }
+    }
+}
+
+impl Default for Odp {
+    fn default() -> Self {
+        Odp { notes: true, hidden: false }
+    }
+}
+
+/// Walks the body of an OpenDocument part and collects its paragraphs
+struct Walker {
+    notes: bool,
+    hidden: bool,
+    hidden_styles: HashSet<String>,
+}
+
+impl Walker {
+    fn new(notes: bool, hidden: bool, parts: &[&Document]) -> Self {
+        let mut hidden_styles = HashSet::new();
+        for doc in parts {
+            for style in doc.descendants().filter(|n| n.has_tag_name((STYLE_NS, "style"))) {
+                let display_none = style.children().any(|n| {
+                    n.has_tag_name((STYLE_NS, "text-properties"))
+                        && (n.attribute((TEXT_NS, "display")) == Some("none") || n.attribute((FO_NS, "display")) == Some("none"))
+                });
+                if let (true, Some(name)) = (display_none, style.attribute((STYLE_NS, "name"))) {
+                    hidden_styles.insert(String::from(name));
+                }
+            }
+        }
+        Walker { notes, hidden, hidden_styles }
+    }
+
+    fn is_hidden(&self, node: Node) -> bool {
+        if self.hidden {
+            return false;
+        }
+        if node.has_tag_name((TEXT_NS, "hidden-text")) {
+            return true;
+        }
+        match node.attribute((TEXT_NS, "style-name")) {
+            Some(style) => self.hidden_styles.contains(style),
+            None => false,
+        }
+    }
+
+    fn is_hidden_paragraph(&self, paragraph: Node) -> bool {
+        !self.hidden && paragraph.descendants().any(|n| n.has_tag_name((TEXT_NS, "hidden-paragraph")) && n.attribute((TEXT_NS, "is-hidden")) != Some("false"))
+    }
+
+    /// Walks block level content, every paragraph and heading becomes a section
+    fn blocks(&self, node: Node, vec: &mut Vec<String>) {
+        for child in node.children().filter(|n| n.is_element()) {
+            if self.is_hidden(child) {
+                continue;
+            }
+            if child.tag_name().name() == "notes" && !self.notes {
+                continue;
+            }
+            if child.has_tag_name((TEXT_NS, "p")) || child.has_tag_name((TEXT_NS, "h")) {
+                let mut nested = Vec::new();
+                let s = self.inline(child, &mut nested);
+                if !self.is_hidden_paragraph(child) {
+                    vec.push(s);
+                }
+                vec.append(&mut nested);
+            } else if child.tag_name().name() == "annotation" || child.tag_name().name().ends_with("-source") || child.has_tag_name((TEXT_NS, "tracked-changes")) {
+                // Comments, index templates and the deleted text of tracked changes
+                continue;
+            } else {
+                self.blocks(child, vec);
+            }
+        }
+    }
+
+    /// Concatenates the inline content of a paragraph. Paragraphs nested in notes and frames are collected separately.
+    fn inline(&self, node: Node, nested: &mut Vec<String>) -> String {
+        let mut s = String::new();
+        for child in node.children() {
+            if child.is_text() {
+                s += child.text().unwrap_or("");
+                continue;
+            }
+            if !child.is_element() || self.is_hidden(child) {
+                continue;
+            }
+            match child.tag_name().name() {
+                "s" => {
+                    let count = child.attribute((TEXT_NS, "c")).and_then(|c| c.parse().ok()).unwrap_or(1);
+                    s += &" ".repeat(count);
+                },
+                "tab" => s += "\t",
+                "line-break" => s += "\n",
+                "note-citation" | "annotation" | "annotation-end" | "bookmark" | "bookmark-start" | "bookmark-end" | "soft-page-break" => (),
+                "note-body" | "frame" | "text-box" | "custom-shape" => self.blocks(child, nested),
+                _ => s += &self.inline(child, nested),
+            }
+        }
+        s
+    }
+}
+
+fn extract_odf(buf: &[u8], headers_footers: bool, notes: bool, hidden: bool) -> Result<Vec<String>, Box<dyn Error>> {
+    let content = read_file_from_zip(buf, "content.xml")?;
+    let content = Document::parse(&content)?;
+    let styles = read_file_from_zip(buf, "styles.xml")?;
+    let styles = Document::parse(&styles)?;
+    let walker = Walker::new(notes, hidden, &[&content, &styles]);
+    let mut vec = Vec::new();
+
+    for body in content.descendants().filter(|n| n.tag_name().name() == "body") {
+        walker.blocks(body, &mut vec);
+    }
+
+    if headers_footers {
+        let parts = ["header", "header-left", "header-first", "footer", "footer-left", "footer-first"];
+        for part in styles.descendants().filter(|n| n.tag_name().namespace() == Some(STYLE_NS) && parts.contains(&n.tag_name().name())) {
+            walker.blocks(part, &mut vec);
+        }
+    }
+
+    Ok(vec)
+}
+
+impl Extract for Odt {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("odt") || (extension.is_none() && infer::odf::is_odt(buf))
+    }
+
+    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        extract_odf(buf, self.headers_footers, false, self.hidden)
+    }
+}
+
+impl Extract for Ods {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("ods") || (extension.is_none() && infer::odf::is_ods(buf))
+    }
+
+    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        extract_odf(buf, self.headers_footers, false, self.hidden)
+    }
+}
+
+impl Extract for Odp {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("odp") || (extension.is_none() && infer::odf::is_odp(buf))
+    }
+
+    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        extract_odf(buf, false, self.notes, self.hidden)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zip_extensions::build_zip;
+
+    const NAMESPACES: &str = r#"xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0"
+        xmlns:style="urn:oasis:names:tc:opendocument:xmlns:style:1.0" xmlns:fo="urn:oasis:names:tc:opendocument:xmlns:xsl-fo-compatible:1.0""#;
+
+    fn build_odt() -> Vec<u8> {
+        let content = format!(r#"<office:document-content {}>
+            <office:automatic-styles>
+                <style:style style:name="T1" style:family="text"><style:text-properties text:display="none"/></style:style>
+            </office:automatic-styles>
+            <office:body><office:text>
+                <text:tracked-changes><text:changed-region><text:deletion><text:p>Deleted text</text:p></text:deletion></text:changed-region></text:tracked-changes>
+                <text:h>Title</text:h>
+                <text:p>Visible<text:s/>text<text:span text:style-name="T1"> hidden by style</text:span>.<text:note><text:note-citation>1</text:note-citation><text:note-body><text:p>A footnote.</text:p></text:note-body></text:note></text:p>
+                <text:p><text:hidden-paragraph text:condition="ooow:1" text:is-hidden="true"/>Hidden paragraph</text:p>
+                <text:p>Shown <text:hidden-text text:condition="ooow:1" text:string-value="secret">secret</text:hidden-text>text</text:p>
+            </office:text></office:body>
+        </office:document-content>"#, NAMESPACES);
+        let styles = format!(r#"<office:document-styles {}>
+            <office:master-styles><style:master-page style:name="Standard">
+                <style:header><text:p>Header text</text:p></style:header>
+            </style:master-page></office:master-styles>
+        </office:document-styles>"#, NAMESPACES);
+        build_zip(&[("content.xml", &content), ("styles.xml", &styles)])
+    }
+
+    #[test]
+    fn skips_hidden_text_and_tracked_deletions() {
+        let buf = build_odt();
+        assert_eq!(Odt::default().extract(&buf).unwrap(), vec!["Title", "Visible text.", "A footnote.", "Shown text", "Header text"]);
+    }
+
+    #[test]
+    fn extracts_hidden_text_when_asked() {
+        let buf = build_odt();
+        let odt = Odt { headers_footers: false, hidden: true };
+        assert_eq!(odt.extract(&buf).unwrap(), vec!["Title", "Visible text hidden by style.", "A footnote.", "Hidden paragraph", "Shown secrettext"]);
+    }
+}
@@ -0,0 +1,471 @@
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use crate::extract::{Extract, ExtractionError, Section};
+use lopdf::{content::Content, Dictionary, Document, Object};
+
+/// Extraction rule for the text layer of .pdf files.
+/// Pages without a text layer that draw images (most likely scans) are reported by [extract_sections](Extract::extract_sections)
+/// as a warning such as "image-only pages: 3, 7", which ends up in the warnings of the [analysis](crate::analysis::Analysis).
+/// When no page has a text layer, extraction fails instead.
+pub struct Pdf {
+    /// Drop lines that are repeated at the top or bottom of most pages, such as running headers, footers and page numbers
+    pub remove_running_lines: bool,
+}
+
+impl Default for Pdf {
+    fn default() -> Self {
+        Pdf { remove_running_lines: true }
+    }
+}
+
+/// The text found on a single page of a pdf
+#[derive(Debug)]
+pub struct PdfPage {
+    /// The page number, starting at 1
+    pub number: u32,
+
+    /// The paragraphs on the page, rebuilt from the text positioning operators
+    pub paragraphs: Vec<String>,
+
+    /// Whether the page draws any images
+    pub has_images: bool,
+}
+
+impl PdfPage {
+    /// A page without a text layer that does draw images, most likely a scan that needs OCR before it can be counted
+    pub fn is_image_only(&self) -> bool {
+        self.has_images && self.paragraphs.is_empty()
+    }
+}
+
+/// A piece of text drawn by a single text showing operator
+struct Fragment {
+    x: f32,
+    y: f32,
+    size: f32,
+    text: String,
+}
+
+/// Maps character codes to unicode text, parsed from a font's ToUnicode stream
+struct CMap {
+    code_length: usize,
+    map: HashMap<u32, String>,
+}
+
+struct Font {
+    encoding: String,
+    to_unicode: Option<CMap>,
+}
+
+impl Font {
+    fn decode(&self, bytes: &[u8]) -> String {
+        match &self.to_unicode {
+            Some(cmap) => {
+                let mut s = String::new();
+                for code in bytes.chunks(cmap.code_length) {
+                    let code = code.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32);
+                    if let Some(t) = cmap.map.get(&code) {
+                        s += t;
+                    }
+                }
+                s
+            },
+            // Composite fonts without a ToUnicode map can't be decoded into meaningful text
+            None if self.encoding.starts_with("Identity") => String::new(),
+            None => Document::decode_text(Some(&self.encoding), bytes),
+        }
+    }
+}
+
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim().trim_start_matches('<').trim_end_matches('>');
+    (0..s.len()).step_by(2).map(|i| s.get(i..i + 2).and_then(|b| u8::from_str_radix(b, 16).ok())).collect()
+}
+
+fn hex_to_code(s: &str) -> Option<(u32, usize)> {
+    let bytes = parse_hex(s)?;
+    Some((bytes.iter().fold(0u32, |acc, b| (acc << 8) | *b as u32), bytes.len()))
+}
+
+fn hex_to_text(s: &str) -> Option<String> {
+    let bytes = parse_hex(s)?;
+    let units: Vec<u16> = bytes.chunks(2).map(|c| ((c[0] as u16) << 8) | *c.get(1).unwrap_or(&0) as u16).collect();
+    String::from_utf16(&units).ok()
+}
+
+fn parse_cmap(content: &[u8]) -> CMap {
+    let content = String::from_utf8_lossy(content);
+    let mut cmap = CMap { code_length: 1, map: HashMap::new() };
+    let mut section = "";
+    for line in content.lines().map(|l| l.trim()) {
+        if line.ends_with("beginbfchar") || line.ends_with("beginbfrange") || line.ends_with("begincodespacerange") {
+            section = line.rsplit(' ').next().unwrap_or("");
+            continue;
+        }
+        if line.starts_with("end") {
+            section = "";
+            continue;
+        }
+        let tokens: Vec<&str> = line.split(['<', '>', '[', ']'])
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .collect();
+        match section {
+            "begincodespacerange" if tokens.len() >= 2 => {
+                if let Some((_, len)) = hex_to_code(tokens[0]) {
+                    cmap.code_length = len.max(1);
+                }
+            },
+            "beginbfchar" if tokens.len() >= 2 => {
+                if let (Some((code, _)), Some(text)) = (hex_to_code(tokens[0]), hex_to_text(tokens[1])) {
+                    cmap.map.insert(code, text);
+                }
+            },
+            "beginbfrange" if tokens.len() >= 3 => {
+                let (lo, hi) = match (hex_to_code(tokens[0]), hex_to_code(tokens[1])) {
+                    (Some((lo, _)), Some((hi, _))) if hi >= lo => (lo, hi),
+                    _ => continue,
+                };
+                if line.contains('[') {
+                    for (code, dst) in (lo..=hi).zip(tokens[2..].iter()) {
+                        if let Some(text) = hex_to_text(dst) {
+                            cmap.map.insert(code, text);
+                        }
+                    }
+                } else if let Some(start) = hex_to_text(tokens[2]) {
+                    let mut units: Vec<u16> = start.encode_utf16().collect();
+                    for code in lo..=hi {
+                        cmap.map.insert(code, String::from_utf16_lossy(&units));
+                        match units.last_mut() {
+                            Some(u) => *u = u.wrapping_add(1),
+                            None => break,
+                        }
+                    }
+                }
+            },
+            _ => (),
+        }
+    }
+    cmap
+}
+
+fn load_font(doc: &Document, font: &Dictionary) -> Font {
+    let to_unicode = font.get(b"ToUnicode")
+        .and_then(Object::as_reference)
+        .and_then(|id| doc.get_object(id))
+        .and_then(Object::as_stream)
+        .ok()
+        .map(|stream| parse_cmap(&stream.decompressed_content().unwrap_or_else(|_| stream.content.clone())));
+    Font { encoding: String::from(font.get_font_encoding()), to_unicode }
+}
+
+fn page_has_images(doc: &Document, page_id: lopdf::ObjectId) -> bool {
+    let (resources, resource_ids) = doc.get_page_resources(page_id);
+    resources.into_iter()
+        .chain(resource_ids.into_iter().filter_map(|id| doc.get_dictionary(id).ok()))
+        .filter_map(|r| r.get(b"XObject").ok())
+        .filter_map(|x| doc.dereference(x).ok().and_then(|(_, x)| x.as_dict().ok()))
+        .flat_map(|x| x.iter().map(|(_, o)| o))
+        .filter_map(|o| doc.dereference(o).ok())
+        .any(|(_, o)| match o.as_stream() {
+            Ok(s) => s.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image"),
+            Err(_) => false,
+        })
+}
+
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+fn multiply(a: &Matrix, b: &Matrix) -> Matrix {
+    [
+        a[0] * b[0] + a[1] * b[2],
+        a[0] * b[1] + a[1] * b[3],
+        a[2] * b[0] + a[3] * b[2],
+        a[2] * b[1] + a[3] * b[3],
+        a[4] * b[0] + a[5] * b[2] + b[4],
+        a[4] * b[1] + a[5] * b[3] + b[5],
+    ]
+}
+
+fn operand_matrix(operands: &[Object]) -> Option<Matrix> {
+    let values: Vec<f32> = operands.iter().filter_map(|o| o.as_float().ok()).collect();
+    values.try_into().ok()
+}
+
+fn show_text(font: Option<&Font>, operands: &[Object]) -> String {
+    let mut s = String::new();
+    for operand in operands {
+        match operand {
+            Object::String(bytes, _) => s += &font.map(|f| f.decode(bytes)).unwrap_or_default(),
+            Object::Array(items) => s += &show_text(font, items),
+            // Large negative kerning inside a TJ array is how many generators encode a word space
+            Object::Integer(_) | Object::Real(_) if operand.as_float().unwrap_or(0.0) < -200.0 && !s.ends_with(' ') => s.push(' '),
+            _ => (),
+        }
+    }
+    s
+}
+
+fn read_fragments(doc: &Document, page_id: lopdf::ObjectId, content: &Content) -> Vec<Fragment> {
+    let fonts: BTreeMap<Vec<u8>, Font> = doc.get_page_fonts(page_id)
+        .into_iter()
+        .map(|(name, font)| (name, load_font(doc, font)))
+        .collect();
+    let mut fragments = Vec::new();
+    let mut ctm = IDENTITY;
+    let mut stack = Vec::new();
+    let mut text_matrix = IDENTITY;
+    let mut line_matrix = IDENTITY;
+    let mut leading = 0.0;
+    let mut font_size = 0.0;
+    let mut font = None;
+
+    for op in &content.operations {
+        let operands = &op.operands;
+        let number = |i: usize| operands.get(i).and_then(|o| o.as_float().ok()).unwrap_or(0.0);
+        let mut shown = None;
+        match op.operator.as_str() {
+            "q" => stack.push(ctm),
+            "Q" => ctm = stack.pop().unwrap_or(IDENTITY),
+            "cm" => if let Some(m) = operand_matrix(operands) { ctm = multiply(&m, &ctm) },
+            "BT" => {
+                text_matrix = IDENTITY;
+                line_matrix = IDENTITY;
+            },
+            "Tf" => {
+                font = operands.first().and_then(|o| o.as_name().ok()).and_then(|n| fonts.get(n));
+                font_size = number(1);
+            },
+            "TL" => leading = number(0),
+            "Td" | "TD" => {
+                if op.operator == "TD" {
+                    leading = -number(1);
+                }
+                line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, number(0), number(1)], &line_matrix);
+                text_matrix = line_matrix;
+            },
+            "Tm" => if let Some(m) = operand_matrix(operands) {
+                line_matrix = m;
+                text_matrix = m;
+            },
+            "T*" => {
+                line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -leading], &line_matrix);
+                text_matrix = line_matrix;
+            },
+            "Tj" | "TJ" => shown = Some(&operands[..]),
+            "'" | "\"" => {
+                line_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, 0.0, -leading], &line_matrix);
+                text_matrix = line_matrix;
+                shown = Some(&operands[operands.len().saturating_sub(1)..]);
+            },
+            _ => (),
+        }
+
+        if let Some(operands) = shown {
+            let text = show_text(font, operands);
+            if text.trim().is_empty() {
+                continue;
+            }
+            let m = multiply(&text_matrix, &ctm);
+            let size = (font_size * (m[2] * m[2] + m[3] * m[3]).sqrt()).abs().max(1.0);
+            // Without glyph widths we estimate the advance, so that the next fragment on the same line is placed sensibly
+            let advance = text.chars().count() as f32 * font_size * 0.5;
+            text_matrix = multiply(&[1.0, 0.0, 0.0, 1.0, advance, 0.0], &text_matrix);
+            fragments.push(Fragment { x: m[4], y: m[5], size, text });
+        }
+    }
+    fragments
+}
+
+fn build_paragraphs(fragments: Vec<Fragment>) -> Vec<String> {
+    // Lines as (y, size, text, x where the last fragment ended)
+    let mut lines: Vec<(f32, f32, String, f32)> = Vec::new();
+    for fragment in fragments {
+        let width = fragment.text.chars().count() as f32 * fragment.size * 0.5;
+        match lines.last_mut() {
+            Some((y, size, text, end)) if (*y - fragment.y).abs() < *size * 0.5 => {
+                if fragment.x > *end + *size * 0.2 && !text.ends_with(' ') && !fragment.text.starts_with(' ') {
+                    text.push(' ');
+                }
+                *text += &fragment.text;
+                *end = fragment.x + width;
+            },
+            _ => lines.push((fragment.y, fragment.size, fragment.text, fragment.x + width)),
+        }
+    }
+
+    let mut paragraphs: Vec<String> = Vec::new();
+    let mut previous: Option<(f32, f32)> = None;
+    for (y, size, text, _) in lines {
+        let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if text.is_empty() {
+            continue;
+        }
+        let continues = match previous {
+            Some((prev_y, prev_size)) => {
+                let gap = prev_y - y;
+                gap > 0.0 && gap < prev_size.max(size) * 1.6 && (prev_size - size).abs() < prev_size * 0.2
+            },
+            None => false,
+        };
+        match paragraphs.last_mut() {
+            Some(paragraph) if continues => {
+                let hyphenated = paragraph.ends_with('-')
+                    && paragraph.chars().rev().nth(1).is_some_and(char::is_alphabetic)
+                    && text.chars().next().is_some_and(char::is_lowercase);
+                if hyphenated {
+                    paragraph.pop();
+                } else {
+                    paragraph.push(' ');
+                }
+                *paragraph += &text;
+            },
+            _ => paragraphs.push(text),
+        }
+        previous = Some((y, size));
+    }
+    paragraphs
+}
+
+/// Normalizes a line for running header detection, so that "Page 3 of 10" and "Page 4 of 10" are considered equal
+fn running_key(s: &str) -> String {
+    s.chars().filter(|c| !c.is_whitespace()).map(|c| if c.is_numeric() { '#' } else { c }).collect()
+}
+
+fn remove_running_lines(pages: &mut [PdfPage]) {
+    let text_pages = pages.iter().filter(|p| !p.paragraphs.is_empty()).count();
+    if text_pages < 3 {
+        return;
+    }
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for page in pages.iter() {
+        let mut edges: Vec<String> = page.paragraphs.first().into_iter().chain(page.paragraphs.last()).map(|p| running_key(p)).collect();
+        edges.dedup();
+        for key in edges {
+            *occurrences.entry(key).or_insert(0) += 1;
+        }
+    }
+    let is_running = |p: &String| occurrences.get(&running_key(p)).is_some_and(|n| *n * 2 > text_pages);
+    for page in pages.iter_mut() {
+        if page.paragraphs.first().is_some_and(is_running) {
+            page.paragraphs.remove(0);
+        }
+        if page.paragraphs.last().is_some_and(is_running) {
+            page.paragraphs.pop();
+        }
+    }
+}
+
+impl Pdf {
+    /// Reads the text layer of every page. Use [is_image_only](PdfPage::is_image_only) to find pages that have no text to count.
+    pub fn pages(&self, buf: &[u8]) -> Result<Vec<PdfPage>, Box<dyn Error>> {
+        let doc = Document::load_mem(buf)?;
+        let mut pages = Vec::new();
+        for (number, page_id) in doc.get_pages() {
+            let content = Content::decode(&doc.get_page_content(page_id)?)?;
+            let has_images = page_has_images(&doc, page_id) || content.operations.iter().any(|op| op.operator == "BI");
+            let paragraphs = build_paragraphs(read_fragments(&doc, page_id, &content));
+            pages.push(PdfPage { number, paragraphs, has_images });
+        }
+        if self.remove_running_lines {
+            remove_running_lines(&mut pages);
+        }
+        Ok(pages)
+    }
+}
+
+impl Extract for Pdf {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("pdf") || (extension.is_none() && infer::archive::is_pdf(buf))
+    }
+
+    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self.extract_sections(buf)?.into_iter().map(|s| s.text).filter(|t| !t.is_empty()).collect())
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let pages = self.pages(buf)?;
+        let scanned: Vec<String> = pages.iter().filter(|p| p.is_image_only()).map(|p| p.number.to_string()).collect();
+        if !scanned.is_empty() && pages.iter().all(|p| p.paragraphs.is_empty()) {
+            return Err(Box::new(ExtractionError(format!("PDF has no text layer, image-only pages: {}", scanned.join(", ")))));
+        }
+        let mut vec: Vec<Section> = pages.into_iter().flat_map(|p| p.paragraphs).map(Section::from).collect();
+        if !scanned.is_empty() {
+            vec.push(Section { warning: Some(format!("image-only pages: {}", scanned.join(", "))), ..Default::default() });
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{analysis::analyze, memory::HashedMemory, segmentation::hashment_sections, unicode::UnicodeRules};
+    use lopdf::{dictionary, Stream};
+
+    /// Builds a pdf with a page per entry: a line of text, or an image when there is no text
+    fn build_pdf(pages: &[Option<&str>]) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+            "Encoding" => "WinAnsiEncoding",
+        });
+        let image_id = doc.add_object(Stream::new(dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => 1,
+            "Height" => 1,
+            "ColorSpace" => "DeviceGray",
+            "BitsPerComponent" => 8,
+        }, vec![0]));
+        let mut kids = Vec::new();
+        for text in pages {
+            let content = match text {
+                Some(t) => format!("BT /F1 12 Tf 72 700 Td ({}) Tj ET", t),
+                None => String::from("q 100 0 0 100 72 600 cm /Im1 Do Q"),
+            };
+            let content_id = doc.add_object(Stream::new(dictionary! {}, content.into_bytes()));
+            let page_id = doc.add_object(dictionary! {
+                "Type" => "Page",
+                "Parent" => pages_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+                "Contents" => content_id,
+                "Resources" => dictionary! {
+                    "Font" => dictionary! { "F1" => font_id },
+                    "XObject" => dictionary! { "Im1" => image_id },
+                },
+            });
+            kids.push(page_id.into());
+        }
+        let count = kids.len() as i64;
+        doc.objects.insert(pages_id, Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => kids, "Count" => count }));
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        let mut buf = Vec::new();
+        doc.save_to(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn reports_image_only_pages_of_mixed_document() {
+        let buf = build_pdf(&[Some("First page text."), None, Some("Third page text."), None]);
+        assert_eq!(Pdf::default().extract(&buf).unwrap(), vec!["First page text.", "Third page text."]);
+
+        let sections = Pdf::default().extract_sections(&buf).unwrap();
+        let analysis = analyze(&hashment_sections(sections, &UnicodeRules), &HashedMemory::new());
+        assert_eq!(analysis.warnings, vec!["image-only pages: 2, 4"]);
+        assert_eq!(analysis.total.segments, 2);
+        assert_eq!(analysis.total.words, 6);
+    }
+
+    #[test]
+    fn fails_without_text_layer() {
+        let buf = build_pdf(&[None, None]);
+        let error = Pdf::default().extract(&buf).unwrap_err();
+        assert_eq!(error.to_string(), "PDF has no text layer, image-only pages: 1, 2");
+    }
+}
@@ -1,21 +1,159 @@
 use std::error::Error;
-use crate::{extract::Extract, zip_extensions::read_files_from_zip, xml_extensions::extract_text_from_nodes};
+use crate::{extract::{Extract, Section}, zip_extensions::{read_file_from_zip, read_files_from_zip, read_relationships}, xml_extensions::extract_drawing_paragraphs};
 
-pub struct Pptx;
+/// Extraction rule for .pptx files. The slides are always extracted, the other parts can be switched on and off.
+pub struct Pptx {
+    /// Extract the speaker notes. Notes are reported as the "notes" part in the [analysis](crate::analysis::Analysis).
+    pub notes: bool,
+
+    /// Extract the text on slide masters
+    pub masters: bool,
+
+    /// Extract the text on slide layouts
+    pub layouts: bool,
+
+    /// Extract titles, series names and category labels of charts
+    pub charts: bool,
+
+    /// Extract the text of SmartArt diagrams
+    pub diagrams: bool,
+
+    /// Extract the alternative text (descr) of pictures and shapes
+    pub alt_text: bool,
+
+    /// Extract slides that are hidden during the slide show (show="0")
+    pub hidden_slides: bool,
+}
+
+impl Default for Pptx {
+    fn default() -> Self {
+        Pptx {
+            notes: true,
+            masters: false,
+            layouts: false,
+            charts: true,
+            diagrams: true,
+            alt_text: true,
+            hidden_slides: true,
+        }
+    }
+}
+
+const R_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// Placeholders that are filled in by PowerPoint itself
+const GENERATED_PLACEHOLDERS: [&str; 2] = ["sldNum", "dt"];
+
+impl Pptx {
+    fn extract_shapes(&self, doc: &roxmltree::Document) -> Vec<String> {
+        let mut vec = Vec::new();
+        for node in doc.root().descendants() {
+            // Shapes in mc:AlternateContent are stored twice, as the mc:Choice for newer versions of PowerPoint and as an mc:Fallback
+            if node.ancestors().any(|n| n.has_tag_name("Fallback")) {
+                continue;
+            }
+            if node.has_tag_name("sp") {
+                let generated = node.descendants()
+                    .filter(|n| n.has_tag_name("ph"))
+                    .any(|n| GENERATED_PLACEHOLDERS.contains(&n.attribute("type").unwrap_or("")));
+                if !generated {
+                    for body in node.children().filter(|n| n.has_tag_name("txBody")) {
+                        vec.append(&mut extract_drawing_paragraphs(body));
+                    }
+                }
+            } else if node.has_tag_name("graphicFrame") {
+                // Tables
+                vec.append(&mut extract_drawing_paragraphs(node));
+            } else if node.has_tag_name("cNvPr") && self.alt_text {
+                if let Some(d) = node.attribute("descr") {
+                    vec.push(String::from(d));
+                }
+            }
+        }
+        vec
+    }
+
+    /// The paths of the slides in presentation order, as listed in ppt/presentation.xml
+    fn slide_paths(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        let presentation = read_file_from_zip(buf, "ppt/presentation.xml")?;
+        let doc = roxmltree::Document::parse(&presentation)?;
+        let relationships = read_relationships(buf, "ppt/presentation.xml")?;
+        let mut vec = Vec::new();
+        for slide in doc.descendants().filter(|n| n.has_tag_name("sldId")) {
+            let id = slide.attribute((R_NS, "id"));
+            if let Some(r) = relationships.iter().find(|r| Some(r.id.as_str()) == id) {
+                vec.push(r.target.clone());
+            }
+        }
+        Ok(vec)
+    }
+}
 
 impl Extract for Pptx {
     fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
-        extension == Some("pptx") || infer::doc::is_pptx(buf)
+        extension == Some("pptx") || (extension.is_none() && infer::doc::is_pptx(buf))
     }
 
-    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
         let mut vec = Vec::new();
-        let slides = read_files_from_zip(buf, "ppt/slides/slide")?;
-        for slide in slides {
+        for path in self.slide_paths(buf)? {
+            let slide = read_file_from_zip(buf, &path)?;
             let doc = roxmltree::Document::parse(&slide)?;
-            let mut texts = extract_text_from_nodes(doc.root().descendants().filter(|n| n.has_tag_name("t")).collect());
-            vec.append(&mut texts)
+            if !self.hidden_slides && doc.root_element().attribute("show") == Some("0") {
+                continue;
+            }
+            vec.extend(self.extract_shapes(&doc).into_iter().map(Section::from));
+
+            // Charts, diagrams and notes are attached to the slide, so they follow the slide order and are skipped with hidden slides
+            for relationship in read_relationships(buf, &path)? {
+                let kind = relationship.kind.rsplit('/').next().unwrap_or("");
+                let wanted = (kind == "chart" && self.charts) || (kind == "diagramData" && self.diagrams) || (kind == "notesSlide" && self.notes);
+                if !wanted {
+                    continue;
+                }
+                let part = read_file_from_zip(buf, &relationship.target)?;
+                let doc = roxmltree::Document::parse(&part)?;
+                if kind == "notesSlide" {
+                    for text in self.extract_shapes(&doc) {
+                        vec.push(Section { text, part: Some(String::from("notes")), ..Default::default() });
+                    }
+                } else {
+                    vec.extend(extract_drawing_paragraphs(doc.root()).into_iter().map(Section::from));
+                }
+            }
+        }
+
+        let mut parts = Vec::new();
+        if self.masters {
+            parts.append(&mut read_files_from_zip(buf, "ppt/slideMasters/slideMaster")?);
+        }
+        if self.layouts {
+            parts.append(&mut read_files_from_zip(buf, "ppt/slideLayouts/slideLayout")?);
         }
+        for part in parts {
+            let doc = roxmltree::Document::parse(&part)?;
+            vec.extend(self.extract_shapes(&doc).into_iter().map(Section::from));
+        }
+
         Ok(vec)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alternate_content_is_extracted_once() {
+        let slide = r#"<p:sld xmlns:p="http://schemas.openxmlformats.org/presentationml/2006/main" xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"
+            xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006"><p:cSld><p:spTree>
+            <p:sp><p:txBody><a:p><a:r><a:t>Title</a:t></a:r></a:p></p:txBody></p:sp>
+            <mc:AlternateContent>
+                <mc:Choice Requires="p14"><p:sp><p:txBody><a:p><a:r><a:t>Equation shape</a:t></a:r></a:p></p:txBody></p:sp></mc:Choice>
+                <mc:Fallback><p:sp><p:txBody><a:p><a:r><a:t>Equation shape</a:t></a:r></a:p></p:txBody></p:sp></mc:Fallback>
+            </mc:AlternateContent>
+        </p:spTree></p:cSld></p:sld>"#;
+        let doc = roxmltree::Document::parse(slide).unwrap();
+        assert_eq!(Pptx::default().extract_shapes(&doc), vec!["Title", "Equation shape"]);
+    }
+}
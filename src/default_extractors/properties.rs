@@ -0,0 +1,144 @@
+use std::error::Error;
+use std::str::from_utf8;
+use crate::{extract::{Extract, Section}, icu::expand};
+
+/// Extraction rule for Java .properties resource bundles.
+/// Every value is extracted with its key, the comment lines right above an entry are used as context.
+/// Files that are not valid UTF-8 are read as ISO-8859-1, the legacy encoding of resource bundles.
+/// Values are MessageFormat patterns, so arguments like {0} are not counted.
+pub struct Properties;
+
+/// Decodes UTF-8, or ISO-8859-1 where every byte is the code point of a character
+fn decode(buf: &[u8]) -> String {
+    match from_utf8(buf) {
+        Ok(s) => String::from(s.trim_start_matches('\u{feff}')),
+        Err(_) => buf.iter().map(|b| *b as char).collect(),
+    }
+}
+
+fn is_comment(line: &str) -> bool {
+    line.starts_with('#') || line.starts_with('!')
+}
+
+/// Joins lines that end with an odd number of backslashes with the next line, without its leading whitespace.
+/// Comment lines are never continued.
+fn logical_lines(s: &str) -> Vec<String> {
+    let mut vec = Vec::new();
+    let mut current: Option<String> = None;
+    for line in s.lines() {
+        let line = match &current {
+            Some(_) => line.trim_start(),
+            None if is_comment(line.trim_start()) => {
+                vec.push(String::from(line));
+                continue;
+            },
+            None => line,
+        };
+        let backslashes = line.chars().rev().take_while(|c| *c == '\\').count();
+        let mut joined = current.take().unwrap_or_default();
+        if backslashes % 2 == 1 {
+            joined.push_str(&line[..line.len() - 1]);
+            current = Some(joined);
+        } else {
+            joined.push_str(line);
+            vec.push(joined);
+        }
+    }
+    vec.extend(current);
+    vec
+}
+
+/// Resolves the escapes of a key or value: \t, \n, \r, \f, \uXXXX and escaped literal characters
+fn unescape(s: &str) -> String {
+    let mut result = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\u{c}'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                if let Some(c) = u32::from_str_radix(&code, 16).ok().and_then(char::from_u32) {
+                    result.push(c);
+                }
+            },
+            Some(c) => result.push(c),
+            None => (),
+        }
+    }
+    result
+}
+
+/// Splits a logical line in its key and value. The key ends at the first unescaped =, : or whitespace.
+fn split_entry(line: &str) -> (String, String) {
+    let mut escaped = false;
+    let mut end = line.len();
+    for (i, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || c.is_whitespace() {
+            end = i;
+            break;
+        }
+    }
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest).trim_start();
+    (unescape(&line[..end]), unescape(rest))
+}
+
+impl Extract for Properties {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("properties")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let mut vec = Vec::new();
+        let mut comments: Vec<String> = Vec::new();
+        for line in logical_lines(&decode(buf)) {
+            let line = line.trim_start();
+            if line.is_empty() {
+                comments.clear();
+            } else if is_comment(line) {
+                comments.push(String::from(line[1..].trim()));
+            } else {
+                let (key, value) = split_entry(line);
+                let context = if comments.is_empty() { None } else { Some(comments.join("\n")) };
+                comments.clear();
+                for text in expand(&value).into_iter().filter(|t| !t.trim().is_empty()) {
+                    vec.push(Section { text, key: Some(key.clone()), context: context.clone(), ..Default::default() });
+                }
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_continuations_escapes_and_comments() {
+        let properties = "# Shown on the start screen\nwelcome = Welcome to \\\n    the app, {0}!\n\ngreeting\\ key:Gr\\u00fc\\u00dfe\n! Not a continuation \\\nsaved=Saved\\tfile\n";
+        let sections = Properties.extract_sections(properties.as_bytes()).unwrap();
+        let sections: Vec<(&str, Option<&str>, Option<&str>)> = sections.iter().map(|s| (s.text.as_str(), s.key.as_deref(), s.context.as_deref())).collect();
+        assert_eq!(sections, vec![
+            ("Welcome to the app, !", Some("welcome"), Some("Shown on the start screen")),
+            ("Grüße", Some("greeting key"), None),
+            ("Saved\tfile", Some("saved"), Some("Not a continuation \\")),
+        ]);
+    }
+
+    #[test]
+    fn reads_latin1() {
+        assert_eq!(Properties.extract(b"title=Caf\xe9").unwrap(), vec!["Café"]);
+    }
+}
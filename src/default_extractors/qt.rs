@@ -0,0 +1,132 @@
+use std::error::Error;
+use std::str::from_utf8;
+use roxmltree::Node;
+use crate::extract::{Extract, Section};
+
+/// Extraction rule for Qt Linguist translation files (.ts).
+/// Only the source text of messages that still need a translation is extracted: messages without a translation or with an unfinished one.
+/// Vanished and obsolete messages are no longer used by the application and are skipped.
+/// Plural (numerus) messages are counted once for every numerus form of the target language.
+pub struct QtLinguist;
+
+fn is_ts(buf: &[u8]) -> bool {
+    match from_utf8(buf).map(roxmltree::Document::parse) {
+        Ok(Ok(doc)) => doc.root_element().has_tag_name("TS"),
+        _ => false,
+    }
+}
+
+/// Removes the %1, %L1 and %n arguments and the & of keyboard mnemonics, unescaping &&
+fn strip_placeholders(s: &str) -> String {
+    let mut result = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '%' if chars.peek() == Some(&'n') => {
+                chars.next();
+            },
+            '%' if chars.peek().is_some_and(|c| c.is_ascii_digit() || *c == 'L') => {
+                chars.next_if_eq(&'L');
+                while chars.next_if(|c| c.is_ascii_digit()).is_some() {}
+            },
+            '&' if chars.peek() == Some(&'&') => {
+                chars.next();
+                result.push('&');
+            },
+            '&' => (),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn child_text<'a>(node: Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    node.children().find(|n| n.has_tag_name(tag)).and_then(|n| n.text())
+}
+
+/// Whether a message still needs to be translated, or None when it should not be counted at all
+fn is_pending(message: Node) -> Option<bool> {
+    let translation = match message.children().find(|n| n.has_tag_name("translation")) {
+        Some(t) => t,
+        None => return Some(true),
+    };
+    match translation.attribute("type") {
+        Some("vanished") | Some("obsolete") => None,
+        Some("unfinished") => Some(true),
+        _ => {
+            let empty = translation.descendants().filter(|n| n.is_text()).all(|n| n.text().unwrap_or("").trim().is_empty());
+            Some(empty)
+        },
+    }
+}
+
+impl Extract for QtLinguist {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        // TypeScript uses the same extension
+        extension == Some("ts") && is_ts(buf)
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let str = from_utf8(buf)?;
+        let doc = roxmltree::Document::parse(str)?;
+        let mut vec = Vec::new();
+        for context in doc.root_element().children().filter(|n| n.has_tag_name("context")) {
+            let context_name = child_text(context, "name").unwrap_or("");
+            for message in context.children().filter(|n| n.has_tag_name("message")) {
+                if is_pending(message) != Some(true) {
+                    continue;
+                }
+                let text = strip_placeholders(child_text(message, "source").unwrap_or(""));
+                if text.trim().is_empty() {
+                    continue;
+                }
+                let key = Some(String::from(message.attribute("id").unwrap_or(context_name)));
+                let comment = child_text(message, "comment").or_else(|| child_text(message, "extracomment")).map(String::from);
+
+                let contexts = match message.attribute("numerus") {
+                    Some("yes") => {
+                        let forms = message.descendants().filter(|n| n.has_tag_name("numerusform")).count().max(1);
+                        (1..=forms).map(|i| Some(match &comment {
+                            Some(comment) => format!("{} (numerus form: {})", comment, i),
+                            None => format!("numerus form: {}", i),
+                        })).collect()
+                    },
+                    _ => vec![comment],
+                };
+                for context in contexts {
+                    vec.push(Section { text: text.clone(), key: key.clone(), context, ..Default::default() });
+                }
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_unfinished_messages_with_numerus_forms() {
+        let ts = br#"<?xml version="1.0" encoding="utf-8"?>
+<!DOCTYPE TS>
+<TS version="2.1" language="fr">
+<context>
+    <name>MainWindow</name>
+    <message><source>Open %1</source><comment>File menu</comment><translation type="unfinished"></translation></message>
+    <message><source>Done</source><translation>Fait</translation></message>
+    <message numerus="yes"><source>%n file(s)</source><extracomment>Status bar</extracomment><translation type="unfinished"><numerusform></numerusform><numerusform></numerusform></translation></message>
+    <message numerus="yes"><source>%n item(s)</source><translation type="unfinished"><numerusform></numerusform></translation></message>
+</context>
+</TS>"#;
+        assert!(QtLinguist.can_extract(ts, Some("ts")));
+        let sections = QtLinguist.extract_sections(ts).unwrap();
+        let sections: Vec<(&str, Option<&str>)> = sections.iter().map(|s| (s.text.as_str(), s.context.as_deref())).collect();
+        assert_eq!(sections, vec![
+            ("Open ", Some("File menu")),
+            (" file(s)", Some("Status bar (numerus form: 1)")),
+            (" file(s)", Some("Status bar (numerus form: 2)")),
+            (" item(s)", Some("numerus form: 1")),
+        ]);
+    }
+}
@@ -0,0 +1,56 @@
+use std::error::Error;
+use std::str::from_utf8;
+use crate::extract::{Extract, Section};
+
+/// Extraction rule for .NET resource files (.resx and .resw).
+/// The value of every string resource is extracted with its name as key and its comment as context.
+/// Resources with a type or mimetype, such as images and serialized objects, are not counted.
+pub struct Resx;
+
+impl Extract for Resx {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("resx") || extension == Some("resw")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let str = from_utf8(buf)?;
+        let doc = roxmltree::Document::parse(str)?;
+        let mut vec = Vec::new();
+        for data in doc.root_element().children().filter(|n| n.has_tag_name("data")) {
+            let name = data.attribute("name").unwrap_or("");
+            // Windows Forms designer metadata, e.g. >>button1.Type
+            if data.attribute("type").is_some() || data.attribute("mimetype").is_some() || name.starts_with(">>") {
+                continue;
+            }
+            let child_text = |tag: &str| data.children().find(|n| n.has_tag_name(tag)).and_then(|n| n.text()).map(String::from);
+            if let Some(text) = child_text("value").filter(|t| !t.trim().is_empty()) {
+                vec.push(Section { text, key: Some(String::from(name)), context: child_text("comment"), ..Default::default() });
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_string_resources_with_comments() {
+        let resx = r#"<?xml version="1.0" encoding="utf-8"?>
+<root>
+  <resheader name="resmimetype"><value>text/microsoft-resx</value></resheader>
+  <data name="Greeting" xml:space="preserve"><value>Hello there</value><comment>Shown after login</comment></data>
+  <data name="Logo" type="System.Drawing.Bitmap, System.Drawing" mimetype="application/x-microsoft.net.object.bytearray.base64"><value>iVBORw0KGgo=</value></data>
+  <data name="&gt;&gt;button1.Type" xml:space="preserve"><value>System.Windows.Forms.Button</value></data>
+  <data name="Empty" xml:space="preserve"><value></value></data>
+  <data name="Farewell" xml:space="preserve"><value>Goodbye</value></data>
+</root>"#;
+        let sections = Resx.extract_sections(resx.as_bytes()).unwrap();
+        let sections: Vec<(&str, Option<&str>, Option<&str>)> = sections.iter().map(|s| (s.text.as_str(), s.key.as_deref(), s.context.as_deref())).collect();
+        assert_eq!(sections, vec![
+            ("Hello there", Some("Greeting"), Some("Shown after login")),
+            ("Goodbye", Some("Farewell"), None),
+        ]);
+    }
+}
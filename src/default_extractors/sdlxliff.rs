@@ -0,0 +1,97 @@
+use std::error::Error;
+use std::str::from_utf8;
+use roxmltree::Node;
+use crate::{extract::{Extract, Section}, progress::{Bilingual, TranslationState, Unit}};
+use super::xliff::{units_1_2, pending_sections};
+
+/// Extraction rule for Trados Studio .sdlxliff files. Only the source text of unconfirmed, unlocked segments is extracted.
+/// The confirmation level, origin, match percentage and lock of every segment (sdl:seg) are used for the [progress](crate::progress::Progress).
+pub struct Sdlxliff;
+
+const SDL_NS: &str = "http://sdl.com/FileTypes/SdlXliff/1.0";
+
+/// Maps a confirmation level onto a state. Segments without confirmation level are not translated yet.
+fn state(conf: Option<&str>, has_target: bool) -> TranslationState {
+    match conf {
+        Some("Translated") => TranslationState::Translated,
+        Some("ApprovedTranslation") => TranslationState::Reviewed,
+        Some("ApprovedSignOff") => TranslationState::Final,
+        // Draft, RejectedTranslation and RejectedSignOff
+        Some(_) => TranslationState::NeedsTranslation,
+        None if has_target => TranslationState::NeedsTranslation,
+        None => TranslationState::New,
+    }
+}
+
+/// Updates a unit with the sdl:seg that describes its segment
+fn read_segment_definition(trans_unit: Node, mid: Option<&str>, unit: &mut Unit) {
+    let seg = trans_unit.descendants()
+        .filter(|n| n.tag_name().namespace() == Some(SDL_NS) && n.has_tag_name("seg"))
+        .find(|n| mid.is_none() || n.attribute("id") == mid);
+    let seg = match seg {
+        Some(s) => s,
+        None => return,
+    };
+    let has_target = unit.target.as_ref().is_some_and(|t| !t.trim().is_empty());
+    unit.state = state(seg.attribute("conf"), has_target);
+    unit.origin = seg.attribute("origin").map(String::from);
+    unit.locked = seg.attribute("locked") == Some("true");
+    unit.match_percent = seg.attribute("percent").and_then(|p| p.parse().ok());
+    // Context matches are stored as 100% matches on both source and target
+    if seg.attribute("text-match") == Some("SourceAndTarget") || unit.origin.as_deref() == Some("document-match") {
+        unit.match_percent = Some(101);
+    }
+}
+
+impl Bilingual for Sdlxliff {
+    fn can_read(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("sdlxliff")
+    }
+
+    fn units(&self, buf: &[u8]) -> Result<Vec<Unit>, Box<dyn Error>> {
+        let str = from_utf8(buf)?;
+        let doc = roxmltree::Document::parse(str)?;
+        Ok(units_1_2(&doc, read_segment_definition))
+    }
+}
+
+impl Extract for Sdlxliff {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        self.can_read(buf, extension)
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        Ok(pending_sections(self.units(buf)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_confirmation_levels_origins_and_locks() {
+        let sdlxliff = br#"<xliff version="1.2" xmlns:sdl="http://sdl.com/FileTypes/SdlXliff/1.0"><file><body>
+            <trans-unit id="a">
+                <source>One. Two. Three.</source>
+                <seg-source><mrk mtype="seg" mid="1">One.</mrk> <mrk mtype="seg" mid="2">Two.</mrk> <mrk mtype="seg" mid="3">Three.</mrk></seg-source>
+                <target><mrk mtype="seg" mid="1">Un.</mrk> <mrk mtype="seg" mid="2">Deux.</mrk> <mrk mtype="seg" mid="3"/></target>
+                <sdl:seg-defs>
+                    <sdl:seg id="1" conf="ApprovedTranslation" origin="tm" percent="100" text-match="SourceAndTarget"/>
+                    <sdl:seg id="2" conf="Draft" origin="mt" locked="true"/>
+                    <sdl:seg id="3"/>
+                </sdl:seg-defs>
+            </trans-unit>
+        </body></file></xliff>"#;
+        let units = Sdlxliff.units(sdlxliff).unwrap();
+        let units: Vec<(&str, TranslationState, Option<&str>, Option<u32>, bool)> = units.iter()
+            .map(|u| (u.source.as_str(), u.state, u.origin.as_deref(), u.match_percent, u.locked))
+            .collect();
+        assert_eq!(units, vec![
+            ("One.", TranslationState::Reviewed, Some("tm"), Some(101), false),
+            ("Two.", TranslationState::NeedsTranslation, Some("mt"), None, true),
+            ("Three.", TranslationState::New, None, None, false),
+        ]);
+        assert_eq!(Sdlxliff.extract(sdlxliff).unwrap(), vec!["Three."]);
+    }
+}
@@ -6,7 +6,7 @@ pub struct Txt;
 
 impl Extract for Txt {
     fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
-        extension == Some("txt") || extension == Some("md")
+        extension == Some("txt")
     }
 
     fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
@@ -1,32 +1,231 @@
 use std::error::Error;
-use crate::{extract::Extract};
+use crate::{extract::{Extract, Section}, progress::{Bilingual, TranslationState, Unit}};
+use roxmltree::Node;
 use std::str::from_utf8;
 
+/// Extraction rule for XLIFF 1.2 and 2.0 files (.xlf, .xliff). Only the source text of segments that still need to be translated is extracted.
+/// Segments are skipped when they are marked translate="no", approved, or have a target with a translated (or later) state.
+/// Use [analyze_progress](crate::progress::analyze_progress) for a breakdown of all segments by state.
 pub struct Xliff;
 
-impl Extract for Xliff {
-    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+/// Maps the target of an XLIFF 1.2 trans-unit onto a state. A target without a state counts as translated.
+fn state_1_2(target: Option<&str>, state: Option<&str>, approved: bool) -> TranslationState {
+    let translated = target.is_some_and(|t| !t.trim().is_empty());
+    let state = match state {
+        _ if !translated => TranslationState::New,
+        Some("new") => TranslationState::New,
+        Some("needs-translation") | Some("needs-adaptation") | Some("needs-l10n") => TranslationState::NeedsTranslation,
+        Some("signed-off") | Some("final") => TranslationState::Final,
+        _ => TranslationState::Translated,
+    };
+    if approved {
+        state.max(TranslationState::Reviewed)
+    } else {
+        state
+    }
+}
+
+/// Maps the target of an XLIFF 2.0 segment onto a state. A target without a state counts as translated.
+fn state_2_0(target: Option<&str>, state: Option<&str>) -> TranslationState {
+    let translated = target.is_some_and(|t| !t.trim().is_empty());
+    match state {
+        _ if !translated => TranslationState::New,
+        Some("initial") => TranslationState::NeedsTranslation,
+        Some("reviewed") => TranslationState::Reviewed,
+        Some("final") => TranslationState::Final,
+        _ => TranslationState::Translated,
+    }
+}
+
+/// Elements that contain native codes instead of text
+const CODE_ELEMENTS: [&str; 10] = ["ph", "bpt", "ept", "it", "x", "bx", "ex", "sc", "ec", "cp"];
+
+/// The text of a source or target, including the text inside inline <g>, <pc> and <mrk> elements, without the inline codes
+pub(crate) fn inline_text(node: Node) -> String {
+    let mut s = String::new();
+    for child in node.children() {
+        if child.is_text() {
+            s.push_str(child.text().unwrap_or(""));
+        } else if child.is_element() && !CODE_ELEMENTS.contains(&child.tag_name().name()) && child.attribute("translate") != Some("no") {
+            s.push_str(&inline_text(child));
+        }
+    }
+    s
+}
+
+/// Whether the node or the closest ancestor that has a translate attribute says it should not be translated
+fn is_translatable(node: Node) -> bool {
+    node.ancestors().find_map(|n| n.attribute("translate")) != Some("no")
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, tag: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(tag))
+}
+
+fn notes(node: Node) -> Option<String> {
+    let notes: Vec<&str> = node.descendants()
+        .filter(|n| n.has_tag_name("note") && !n.ancestors().any(|a| a.has_tag_name("alt-trans")))
+        .filter_map(|n| n.text())
+        .collect();
+    if notes.is_empty() { None } else { Some(notes.join("\n")) }
+}
+
+/// The segments (<mrk mtype="seg">) of a seg-source or target, with their mid
+fn segment_markers<'a, 'input>(node: Node<'a, 'input>) -> Vec<(Option<&'a str>, Node<'a, 'input>)> {
+    node.descendants()
+        .filter(|n| n.has_tag_name("mrk") && n.attribute("mtype") == Some("seg"))
+        .map(|n| (n.attribute("mid"), n))
+        .collect()
+}
+
+/// Reads the units of an XLIFF 1.2 file. A trans-unit with a segmented seg-source becomes a unit per segment.
+/// Dialects such as SDLXLIFF update every unit from the metadata in their own namespace, given the trans-unit and the mid of the segment.
+pub(crate) fn units_1_2<F: Fn(Node, Option<&str>, &mut Unit)>(doc: &roxmltree::Document, dialect: F) -> Vec<Unit> {
+    let mut vec = Vec::new();
+    for trans_unit in doc.descendants().filter(|n| n.has_tag_name("trans-unit") && is_translatable(*n)) {
+        let id = trans_unit.attribute("id");
+        let target = child(trans_unit, "target");
+        let state = target.and_then(|t| t.attribute("state"));
+        let origin = target.and_then(|t| t.attribute("state-qualifier")).map(String::from);
+        let approved = trans_unit.attribute("approved") == Some("yes");
+        let note = notes(trans_unit);
+        // The best translation memory match offered in alt-trans, e.g. match-quality="85" or "85%"
+        let match_percent = trans_unit.children()
+            .filter(|n| n.has_tag_name("alt-trans"))
+            .filter_map(|n| n.attribute("match-quality")?.trim().trim_end_matches('%').parse::<f32>().ok())
+            .map(|p| p.round() as u32)
+            .max();
+
+        let segments = child(trans_unit, "seg-source").map(segment_markers).unwrap_or_default();
+        if segments.is_empty() {
+            if let Some(source) = child(trans_unit, "source") {
+                let target = target.map(inline_text);
+                let mut unit = Unit {
+                    id: id.map(String::from),
+                    source: inline_text(source),
+                    state: state_1_2(target.as_deref(), state, approved),
+                    target,
+                    origin,
+                    match_percent,
+                    locked: false,
+                    note,
+                };
+                dialect(trans_unit, None, &mut unit);
+                vec.push(unit);
+            }
+            continue;
+        }
+        let target_segments = target.map(segment_markers).unwrap_or_default();
+        for (mid, source) in segments {
+            let target = target_segments.iter().find(|(m, _)| *m == mid).map(|(_, t)| inline_text(*t));
+            let mut unit = Unit {
+                id: Some(format!("{}/{}", id.unwrap_or(""), mid.unwrap_or(""))),
+                source: inline_text(source),
+                state: state_1_2(target.as_deref(), state, approved),
+                target,
+                origin: origin.clone(),
+                match_percent,
+                locked: false,
+                note: note.clone(),
+            };
+            dialect(trans_unit, mid, &mut unit);
+            vec.push(unit);
+        }
+    }
+    vec
+}
+
+/// Reads the segments of an XLIFF 2.0 file
+fn units_2_0(doc: &roxmltree::Document) -> Vec<Unit> {
+    let mut vec = Vec::new();
+    for unit in doc.descendants().filter(|n| n.has_tag_name("unit") && is_translatable(*n)) {
+        let id = unit.attribute("id").unwrap_or("");
+        let note = notes(unit);
+        for segment in unit.children().filter(|n| n.has_tag_name("segment")) {
+            let source = match child(segment, "source") {
+                Some(s) => inline_text(s),
+                None => continue,
+            };
+            let target = child(segment, "target").map(inline_text);
+            vec.push(Unit {
+                id: Some(match segment.attribute("id") {
+                    Some(s) => format!("{}/{}", id, s),
+                    None => String::from(id),
+                }),
+                source,
+                state: state_2_0(target.as_deref(), segment.attribute("state")),
+                target,
+                origin: segment.attribute("subState").map(String::from),
+                match_percent: None,
+                locked: false,
+                note: note.clone(),
+            });
+        }
+    }
+    vec
+}
+
+impl Bilingual for Xliff {
+    fn can_read(&self, _buf: &[u8], extension: Option<&str>) -> bool {
         extension == Some("xlf") || extension == Some("xliff")
     }
 
-    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+    fn units(&self, buf: &[u8]) -> Result<Vec<Unit>, Box<dyn Error>> {
         let str = from_utf8(buf)?;
-        let mut vec = Vec::new();          
-        let doc = roxmltree::Document::parse(str)?;        
-        for segment in doc.descendants().filter(|n| n.has_tag_name("segment") ) {
-            if segment.descendants().any(|n| n.has_tag_name("target") && match n.text() {
-                Some(x) => x.len() > 0,
-                None => false,
-            }) {
-                continue;
-            }
-            for source in segment.descendants().filter(|n| n.has_tag_name("source")) {
-                match source.text() {
-                    Some(t) => vec.push(String::from(t)),
-                    None => (),
-                }
-            }
+        let doc = roxmltree::Document::parse(str)?;
+        if doc.root_element().attribute("version").is_some_and(|v| v.starts_with('2')) {
+            Ok(units_2_0(&doc))
+        } else {
+            Ok(units_1_2(&doc, |_, _, _| ()))
         }
-        Ok(vec)
     }
-}
\ No newline at end of file
+}
+
+impl Extract for Xliff {
+    fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
+        self.can_read(buf, extension)
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        Ok(pending_sections(self.units(buf)?))
+    }
+}
+
+/// The source text of the units that still need to be translated and are not locked
+pub(crate) fn pending_sections(units: Vec<Unit>) -> Vec<Section> {
+    units.into_iter()
+        .filter(|u| u.state.is_pending() && !u.locked && !u.source.trim().is_empty())
+        .map(|u| Section { text: u.source, key: u.id, context: u.note, ..Default::default() })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_segmented_units_1_2() {
+        let xliff = br#"<xliff version="1.2"><file source-language="en" target-language="fr"><body>
+            <trans-unit id="1">
+                <source>Hello <g id="b">world</g>. Bye.</source>
+                <seg-source><mrk mtype="seg" mid="1">Hello <g id="b">world</g>.</mrk> <mrk mtype="seg" mid="2">Bye<x id="x"/>.</mrk></seg-source>
+                <target state="translated" state-qualifier="exact-match"><mrk mtype="seg" mid="1">Bonjour <g id="b">monde</g>.</mrk></target>
+                <note>Greeting</note>
+            </trans-unit>
+            <trans-unit id="2" approved="yes"><source>Done</source><target state="needs-translation">Fait</target></trans-unit>
+            <trans-unit id="3" translate="no"><source>Acme</source></trans-unit>
+            <trans-unit id="4"><source>Later</source><target state="needs-adaptation">Plus tard</target></trans-unit>
+        </body></file></xliff>"#;
+        let units = Xliff.units(xliff).unwrap();
+        let units: Vec<(Option<&str>, &str, TranslationState, Option<&str>, bool)> = units.iter()
+            .map(|u| (u.id.as_deref(), u.source.as_str(), u.state, u.origin.as_deref(), u.locked))
+            .collect();
+        assert_eq!(units, vec![
+            (Some("1/1"), "Hello world.", TranslationState::Translated, Some("exact-match"), false),
+            (Some("1/2"), "Bye.", TranslationState::New, Some("exact-match"), false),
+            (Some("2"), "Done", TranslationState::Reviewed, None, false),
+            (Some("4"), "Later", TranslationState::NeedsTranslation, None, false),
+        ]);
+        assert_eq!(Xliff.extract(xliff).unwrap(), vec!["Bye.", "Later"]);
+    }
+}
@@ -1,17 +1,208 @@
 use std::error::Error;
-use crate::{extract::Extract, zip_extensions::read_file_from_zip, xml_extensions::extract_text_from_nodes};
+use std::collections::HashSet;
+use roxmltree::Node;
+use crate::{extract::Extract, zip_extensions::{read_file_from_zip, read_relationships}};
 
-pub struct Xlsx;
+/// Extraction rule for .xlsx files. The worksheets are walked cell by cell, so every occurrence of a string is counted in sheet order.
+pub struct Xlsx {
+    /// Extract the names of the sheets
+    pub sheet_names: bool,
+
+    /// Extract sheets that are hidden
+    pub hidden_sheets: bool,
+
+    /// Extract rows that are hidden
+    pub hidden_rows: bool,
+
+    /// Extract columns that are hidden
+    pub hidden_columns: bool,
+
+    /// Extract the cached results of formula cells
+    pub formulas: bool,
+
+    /// Extract cell comments
+    pub comments: bool,
+
+    /// Extract chart titles and axis titles
+    pub charts: bool,
+
+    /// Only extract the columns whose header (the value in the first row) is in this list. All columns are extracted when empty.
+    /// The header row itself is not extracted when columns are picked.
+    pub header_columns: Vec<String>,
+}
+
+impl Default for Xlsx {
+    fn default() -> Self {
+        Xlsx {
+            sheet_names: true,
+            hidden_sheets: false,
+            hidden_rows: false,
+            hidden_columns: false,
+            formulas: false,
+            comments: false,
+            charts: true,
+            header_columns: Vec::new(),
+        }
+    }
+}
+
+const R_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+/// The text of a shared or inline string, without the phonetic (rPh) runs
+fn string_item_text(node: Node) -> String {
+    node.descendants()
+        .filter(|n| n.has_tag_name("t") && !n.ancestors().any(|a| a.has_tag_name("rPh")))
+        .filter_map(|n| n.text())
+        .collect()
+}
+
+/// Converts the column letters of a cell reference such as "AB12" to a zero based column index
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: Vec<char> = reference.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+    if letters.is_empty() {
+        return None;
+    }
+    Some(letters.iter().fold(0, |acc, c| acc * 26 + (c.to_ascii_uppercase() as usize - 'A' as usize + 1)) - 1)
+}
+
+impl Xlsx {
+    /// The text of a cell, if it contains a string that should be extracted
+    fn cell_text(&self, cell: Node, shared_strings: &[String]) -> Option<String> {
+        if !self.formulas && cell.children().any(|n| n.has_tag_name("f")) {
+            return None;
+        }
+        let value = || cell.children().find(|n| n.has_tag_name("v")).and_then(|v| v.text());
+        match cell.attribute("t") {
+            Some("s") => value().and_then(|v| v.parse::<usize>().ok()).and_then(|i| shared_strings.get(i)).cloned(),
+            Some("inlineStr") => cell.children().find(|n| n.has_tag_name("is")).map(string_item_text),
+            Some("str") => value().map(String::from),
+            _ => None,
+        }
+    }
+
+    fn extract_sheet(&self, sheet: &roxmltree::Document, shared_strings: &[String]) -> Vec<String> {
+        let mut hidden_columns = HashSet::new();
+        if !self.hidden_columns {
+            for col in sheet.descendants().filter(|n| n.has_tag_name("col") && matches!(n.attribute("hidden"), Some("1") | Some("true"))) {
+                let min: usize = col.attribute("min").and_then(|m| m.parse().ok()).unwrap_or(1);
+                let max: usize = col.attribute("max").and_then(|m| m.parse().ok()).unwrap_or(min);
+                hidden_columns.extend(min.saturating_sub(1)..max);
+            }
+        }
+
+        let mut picked_columns: Option<HashSet<usize>> = None;
+        // The first row holds the headers when columns are picked, also when it is hidden
+        let mut header = !self.header_columns.is_empty();
+        let mut vec = Vec::new();
+        for row in sheet.descendants().filter(|n| n.has_tag_name("row")) {
+            if !header && !self.hidden_rows && matches!(row.attribute("hidden"), Some("1") | Some("true")) {
+                continue;
+            }
+            let mut cells = Vec::new();
+            let mut next_column = 0;
+            for cell in row.children().filter(|n| n.has_tag_name("c")) {
+                let column = cell.attribute("r").and_then(column_index).unwrap_or(next_column);
+                next_column = column + 1;
+                if hidden_columns.contains(&column) {
+                    continue;
+                }
+                if let Some(text) = self.cell_text(cell, shared_strings) {
+                    cells.push((column, text));
+                }
+            }
+
+            if header {
+                header = false;
+                picked_columns = Some(cells.iter().filter(|(_, t)| self.header_columns.contains(t)).map(|(c, _)| *c).collect());
+                continue;
+            }
+            for (column, text) in cells {
+                let picked = match &picked_columns {
+                    Some(p) => p.contains(&column),
+                    None => true,
+                };
+                if picked {
+                    vec.push(text);
+                }
+            }
+        }
+        vec
+    }
+}
 
 impl Extract for Xlsx {
     fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
-        extension == Some("xlsx") || infer::doc::is_xlsx(buf)
+        extension == Some("xlsx") || (extension.is_none() && infer::doc::is_xlsx(buf))
     }
 
     fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
-        let shared_strings = read_file_from_zip(buf, "xl/sharedStrings.xml")?;
-        let doc = roxmltree::Document::parse(&shared_strings)?;
-        let texts = doc.root().descendants().filter(|n| n.has_tag_name("t")).collect();
-        Ok(extract_text_from_nodes(texts))
+        // Workbooks that only use inline strings don't have a shared strings part
+        let shared_strings = match read_file_from_zip(buf, "xl/sharedStrings.xml") {
+            Ok(s) => {
+                let doc = roxmltree::Document::parse(&s)?;
+                doc.root_element().children().filter(|n| n.has_tag_name("si")).map(string_item_text).collect()
+            },
+            Err(_) => Vec::new(),
+        };
+
+        let workbook = read_file_from_zip(buf, "xl/workbook.xml")?;
+        let workbook = roxmltree::Document::parse(&workbook)?;
+        let relationships = read_relationships(buf, "xl/workbook.xml")?;
+        let mut vec = Vec::new();
+
+        for sheet in workbook.descendants().filter(|n| n.has_tag_name("sheet")) {
+            if !self.hidden_sheets && matches!(sheet.attribute("state"), Some("hidden") | Some("veryHidden")) {
+                continue;
+            }
+            let path = match relationships.iter().find(|r| Some(r.id.as_str()) == sheet.attribute((R_NS, "id"))) {
+                Some(r) => r.target.clone(),
+                None => continue,
+            };
+            if self.sheet_names {
+                if let Some(name) = sheet.attribute("name") {
+                    vec.push(String::from(name));
+                }
+            }
+
+            let xml = read_file_from_zip(buf, &path)?;
+            let doc = roxmltree::Document::parse(&xml)?;
+            vec.append(&mut self.extract_sheet(&doc, &shared_strings));
+
+            for relationship in read_relationships(buf, &path)? {
+                let kind = relationship.kind.rsplit('/').next().unwrap_or("");
+                if kind == "comments" && self.comments {
+                    let comments = read_file_from_zip(buf, &relationship.target)?;
+                    let doc = roxmltree::Document::parse(&comments)?;
+                    vec.extend(doc.descendants().filter(|n| n.has_tag_name("text")).map(string_item_text));
+                } else if kind == "drawing" && self.charts {
+                    for chart in read_relationships(buf, &relationship.target)?.into_iter().filter(|r| r.kind.ends_with("/chart")) {
+                        // Series names and categories are copies of cell values that were already counted, so only titles are extracted
+                        let chart = read_file_from_zip(buf, &chart.target)?;
+                        let doc = roxmltree::Document::parse(&chart)?;
+                        for paragraph in doc.descendants().filter(|n| n.has_tag_name("p")) {
+                            vec.push(paragraph.descendants().filter(|n| n.has_tag_name("t")).filter_map(|n| n.text()).collect());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_columns_by_hidden_header_row() {
+        let sheet = r#"<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>
+            <row r="1" hidden="1"><c r="A1" t="inlineStr"><is><t>id</t></is></c><c r="B1" t="inlineStr"><is><t>text</t></is></c></row>
+            <row r="2"><c r="A2" t="inlineStr"><is><t>key.one</t></is></c><c r="B2" t="inlineStr"><is><t>First text</t></is></c></row>
+            <row r="3" hidden="1"><c r="A3" t="inlineStr"><is><t>key.two</t></is></c><c r="B3" t="inlineStr"><is><t>Hidden text</t></is></c></row>
+        </sheetData></worksheet>"#;
+        let doc = roxmltree::Document::parse(sheet).unwrap();
+        let xlsx = Xlsx { header_columns: vec![String::from("text")], ..Default::default() };
+        assert_eq!(xlsx.extract_sheet(&doc, &[]), vec!["First text"]);
     }
-}
\ No newline at end of file
+}
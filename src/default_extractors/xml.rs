@@ -1,17 +1,502 @@
 use std::error::Error;
-use crate::{extract::Extract, xml_extensions::extract_text_from_node};
+use crate::extract::{Extract, Section};
+use roxmltree::Node;
 use std::str::from_utf8;
 
-pub struct Xml;
+/// Extraction rule for generic XML files, driven by W3C ITS 2.0 rules.
+/// Without rules every element starts a new section and all element content is translatable, attributes are not.
+///
+/// Global rules come from [rules](Xml::rules) and from its:rules elements in the document, later rules take precedence.
+/// The local attributes its:translate, its:withinText, its:locNote and xml:id override the global rules.
+/// Sections are keyed by the ID value of their element and get the localization note as context.
+#[derive(Default)]
+pub struct Xml {
+    /// Global ITS rules, e.g. loaded from a rules file with [from_xml](ItsRules::from_xml). Applied before the rules embedded in the document.
+    pub rules: ItsRules,
+
+    /// Names of elements that are part of the text of their parent, such as b or i. Shorthand for a withinTextRule with withinText="yes".
+    pub elements_within_text: Vec<String>,
+}
+
+const ITS_NS: &str = "http://www.w3.org/2005/11/its";
+const XML_NS: &str = "http://www.w3.org/XML/1998/namespace";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Axis {
+    Child,
+    Descendant,
+}
+
+#[derive(Clone)]
+enum Predicate {
+    HasAttribute(String),
+    AttributeEquals(String, String),
+    AttributeContains(String, String),
+    Not(Box<Predicate>),
+}
+
+impl Predicate {
+    fn matches(&self, node: Node) -> bool {
+        match self {
+            Predicate::HasAttribute(a) => attribute_by_name(node, a).is_some(),
+            Predicate::AttributeEquals(a, v) => attribute_by_name(node, a) == Some(v.as_str()),
+            Predicate::AttributeContains(a, v) => attribute_by_name(node, a).is_some_and(|value| value.contains(v.as_str())),
+            Predicate::Not(p) => !p.matches(node),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Step {
+    axis: Axis,
+    attribute: bool,
+    namespace: Option<String>,
+    /// The local name, or * for any
+    name: String,
+    predicates: Vec<Predicate>,
+}
+
+impl Step {
+    fn matches_name(&self, namespace: Option<&str>, name: &str) -> bool {
+        (self.name == "*" || self.name == name) && (self.namespace.is_none() || self.namespace.as_deref() == namespace)
+    }
+
+    fn matches_element(&self, node: Node) -> bool {
+        !self.attribute
+            && node.is_element()
+            && self.matches_name(node.tag_name().namespace(), node.tag_name().name())
+            && self.predicates.iter().all(|p| p.matches(node))
+    }
+}
+
+/// An attribute given as its qualified name in a selector (e.g. xml:lang) or as its local name
+fn attribute_by_name<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    let local = name.rsplit(':').next().unwrap_or(name);
+    node.attributes().iter().find(|a| a.name() == local).map(|a| a.value())
+}
+
+/// Whether the element matches the steps, where the last step applies to the element itself
+fn matches_steps(node: Node, steps: &[Step]) -> bool {
+    let (last, rest) = match steps.split_last() {
+        Some(s) => s,
+        None => return node.is_root(),
+    };
+    if !last.matches_element(node) {
+        return false;
+    }
+    match last.axis {
+        Axis::Child => node.parent().is_some_and(|p| matches_context(p, rest)),
+        Axis::Descendant => node.ancestors().skip(1).any(|a| matches_context(a, rest)),
+    }
+}
+
+fn matches_context(node: Node, steps: &[Step]) -> bool {
+    if steps.is_empty() {
+        node.is_root()
+    } else {
+        node.is_element() && matches_steps(node, steps)
+    }
+}
+
+/// A subset of XPath that covers the selectors used in ITS rules: absolute and relative location paths with child and descendant steps,
+/// name tests (with namespace prefixes and *), attribute steps, unions and predicates on attributes (@a, @a='v', contains(@a, 'v') and not(...)).
+/// Unprefixed names match elements in any namespace.
+#[derive(Clone)]
+struct Selector(Vec<Vec<Step>>);
+
+/// Splits on a separator that is not inside brackets, parentheses or quotes
+fn split_top_level(s: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '\'' | '"' if quote == Some(c) => quote = None,
+            '\'' | '"' if quote.is_none() => quote = Some(c),
+            '[' | '(' if quote.is_none() => depth += 1,
+            ']' | ')' if quote.is_none() => depth -= 1,
+            c if c == separator && depth == 0 && quote.is_none() => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            },
+            _ => (),
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn parse_predicate(s: &str) -> Option<Predicate> {
+    let s = s.trim();
+    let unquote = |v: &str| String::from(v.trim().trim_matches(|c| c == '\'' || c == '"'));
+    if let Some(inner) = s.strip_prefix("not(").and_then(|s| s.strip_suffix(')')) {
+        return parse_predicate(inner).map(|p| Predicate::Not(Box::new(p)));
+    }
+    if let Some(inner) = s.strip_prefix("contains(").and_then(|s| s.strip_suffix(')')) {
+        let (attribute, value) = inner.split_once(',')?;
+        return Some(Predicate::AttributeContains(String::from(attribute.trim().strip_prefix('@')?), unquote(value)));
+    }
+    let attribute = s.strip_prefix('@')?;
+    match attribute.split_once('=') {
+        Some((name, value)) => Some(Predicate::AttributeEquals(String::from(name.trim()), unquote(value))),
+        None => Some(Predicate::HasAttribute(String::from(attribute.trim()))),
+    }
+}
+
+impl Selector {
+    /// Parses a selector, resolving namespace prefixes with the namespaces in scope of the rule element
+    fn parse(selector: &str, rule: Node) -> Selector {
+        let mut paths = Vec::new();
+        for path in split_top_level(selector, '|') {
+            let path = path.trim();
+            // Relative selectors are evaluated against the document, so they can match anywhere
+            let path = if path.starts_with('/') { String::from(path) } else { format!("//{}", path) };
+            let mut steps = Vec::new();
+            let mut axis = Axis::Child;
+            for part in split_top_level(&path, '/').into_iter().skip(1) {
+                if part.is_empty() {
+                    axis = Axis::Descendant;
+                    continue;
+                }
+                let (test, predicates) = match part.find('[') {
+                    Some(i) => (&part[..i], &part[i..]),
+                    None => (part, ""),
+                };
+                let predicates = predicates.split(']')
+                    .filter_map(|p| p.trim().strip_prefix('['))
+                    .filter_map(parse_predicate)
+                    .collect();
+                let (attribute, test) = match test.strip_prefix('@').or_else(|| test.strip_prefix("attribute::")) {
+                    Some(t) => (true, t),
+                    None => (false, test.strip_prefix("child::").unwrap_or(test)),
+                };
+                let (namespace, name) = match test.split_once(':') {
+                    Some(("xml", name)) => (Some(String::from(XML_NS)), name),
+                    Some((prefix, name)) => {
+                        let uri = rule.namespaces().iter().find(|n| n.name() == Some(prefix)).map(|n| String::from(n.uri()));
+                        (uri, name)
+                    },
+                    None => (None, test),
+                };
+                steps.push(Step { axis, attribute, namespace, name: String::from(name), predicates });
+                axis = Axis::Child;
+            }
+            if !steps.is_empty() {
+                paths.push(steps);
+            }
+        }
+        Selector(paths)
+    }
+
+    fn selects_element(&self, node: Node) -> bool {
+        self.0.iter().any(|steps| matches_steps(node, steps))
+    }
+
+    fn selects_attribute(&self, owner: Node, attribute: &roxmltree::Attribute) -> bool {
+        self.0.iter().any(|steps| {
+            let (last, rest) = match steps.split_last() {
+                Some(s) => s,
+                None => return false,
+            };
+            if !last.attribute || !last.matches_name(attribute.namespace(), attribute.name()) {
+                return false;
+            }
+            match last.axis {
+                Axis::Child => matches_context(owner, rest),
+                Axis::Descendant => owner.ancestors().any(|a| matches_context(a, rest)),
+            }
+        })
+    }
+}
+
+/// Points to a value relative to the selected element: one of its attributes or the text of a child element
+#[derive(Clone)]
+enum Pointer {
+    Attribute(String),
+    Element(String),
+}
+
+impl Pointer {
+    fn parse(s: &str) -> Pointer {
+        let s = s.trim();
+        match s.strip_prefix('@') {
+            Some(a) => Pointer::Attribute(String::from(a)),
+            None => Pointer::Element(String::from(s.rsplit(':').next().unwrap_or(s))),
+        }
+    }
+
+    fn resolve(&self, node: Node) -> Option<String> {
+        match self {
+            Pointer::Attribute(a) => attribute_by_name(node, a).map(String::from),
+            Pointer::Element(e) => node.children().find(|n| n.has_tag_name(e.as_str())).map(|n| n.descendants().filter(|t| t.is_text()).filter_map(|t| t.text()).collect()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum WithinText {
+    Yes,
+    No,
+    Nested,
+}
+
+impl WithinText {
+    fn parse(s: &str) -> Option<WithinText> {
+        match s {
+            "yes" => Some(WithinText::Yes),
+            "no" => Some(WithinText::No),
+            "nested" => Some(WithinText::Nested),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Rule {
+    Translate(Selector, bool),
+    WithinText(Selector, WithinText),
+    IdValue(Selector, Pointer),
+    LocNote(Selector, String),
+    LocNotePointer(Selector, Pointer),
+}
+
+/// A set of W3C ITS 2.0 global rules. Supported are translateRule, withinTextRule, idValueRule and locNoteRule.
+#[derive(Default, Clone)]
+pub struct ItsRules(Vec<Rule>);
+
+impl ItsRules {
+    /// Reads the global rules of all its:rules elements in an XML document, such as an external rules file.
+    /// # Examples
+    /// ```
+    /// use filecount::default_extractors::xml::{ItsRules, Xml};
+    /// use filecount::extract::Extract;
+    ///
+    /// let rules = br#"<its:rules xmlns:its="http://www.w3.org/2005/11/its" version="2.0">
+    ///     <its:translateRule selector="//code" translate="no"/>
+    /// </its:rules>"#;
+    /// let rule = Xml { rules: ItsRules::from_xml(rules).unwrap(), ..Default::default() };
+    ///
+    /// let texts = rule.extract(b"<doc><p>Hello</p><code>let x = 1;</code></doc>").unwrap();
+    /// assert_eq!(texts, vec!["Hello"]);
+    /// ```
+    pub fn from_xml(buf: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let str = from_utf8(buf)?;
+        let doc = roxmltree::Document::parse(str)?;
+        Ok(ItsRules::from_document(&doc))
+    }
+
+    fn from_document(doc: &roxmltree::Document) -> Self {
+        let mut rules = Vec::new();
+        for node in doc.descendants().filter(|n| n.has_tag_name((ITS_NS, "rules"))) {
+            for rule in node.children().filter(|n| n.is_element() && n.tag_name().namespace() == Some(ITS_NS)) {
+                let selector = match rule.attribute("selector") {
+                    Some(s) => Selector::parse(s, rule),
+                    None => continue,
+                };
+                match rule.tag_name().name() {
+                    "translateRule" => rules.push(Rule::Translate(selector, rule.attribute("translate") == Some("yes"))),
+                    "withinTextRule" => {
+                        if let Some(w) = rule.attribute("withinText").and_then(WithinText::parse) {
+                            rules.push(Rule::WithinText(selector, w));
+                        }
+                    },
+                    "idValueRule" => {
+                        if let Some(p) = rule.attribute("idValue") {
+                            rules.push(Rule::IdValue(selector, Pointer::parse(p)));
+                        }
+                    },
+                    "locNoteRule" => {
+                        if let Some(p) = rule.attribute("locNotePointer") {
+                            rules.push(Rule::LocNotePointer(selector, Pointer::parse(p)));
+                        } else if let Some(note) = rule.children().find(|n| n.has_tag_name((ITS_NS, "locNote"))) {
+                            rules.push(Rule::LocNote(selector, note.descendants().filter(|t| t.is_text()).filter_map(|t| t.text()).collect()));
+                        }
+                    },
+                    _ => (),
+                }
+            }
+        }
+        ItsRules(rules)
+    }
+
+    /// Adds the rules of another set, which take precedence over the rules in this set
+    pub fn extend(&mut self, other: ItsRules) {
+        self.0.extend(other.0);
+    }
+}
+
+/// The ITS information of an element, as far as it is set by the rules or local attributes
+#[derive(Default)]
+struct Properties {
+    translate: Option<bool>,
+    within_text: Option<WithinText>,
+    id: Option<String>,
+    loc_note: Option<String>,
+}
+
+/// Walks the document and builds a section for every element that is not within text
+struct Walker<'r> {
+    rules: &'r ItsRules,
+    elements_within_text: &'r [String],
+    sections: Vec<Section>,
+    current: String,
+    key: Option<String>,
+    context: Option<String>,
+    /// Translatable attributes and nested sections of the current section, which are added after its text
+    attributes: Vec<Section>,
+}
+
+impl<'r> Walker<'r> {
+    fn properties(&self, node: Node) -> Properties {
+        let mut properties = Properties::default();
+        if self.elements_within_text.iter().any(|e| e == node.tag_name().name()) {
+            properties.within_text = Some(WithinText::Yes);
+        }
+        for rule in &self.rules.0 {
+            match rule {
+                Rule::Translate(s, t) if s.selects_element(node) => properties.translate = Some(*t),
+                Rule::WithinText(s, w) if s.selects_element(node) => properties.within_text = Some(*w),
+                Rule::IdValue(s, p) if s.selects_element(node) => properties.id = p.resolve(node).or(properties.id),
+                Rule::LocNote(s, n) if s.selects_element(node) => properties.loc_note = Some(n.clone()),
+                Rule::LocNotePointer(s, p) if s.selects_element(node) => properties.loc_note = p.resolve(node).or(properties.loc_note),
+                _ => (),
+            }
+        }
+        if let Some(t) = node.attribute((ITS_NS, "translate")) {
+            properties.translate = Some(t == "yes");
+        }
+        if let Some(w) = node.attribute((ITS_NS, "withinText")).and_then(WithinText::parse) {
+            properties.within_text = Some(w);
+        }
+        if let Some(n) = node.attribute((ITS_NS, "locNote")) {
+            properties.loc_note = Some(String::from(n));
+        }
+        if let Some(id) = node.attribute((XML_NS, "id")) {
+            properties.id = Some(String::from(id));
+        }
+        properties
+    }
+
+    fn is_translatable_attribute(&self, owner: Node, attribute: &roxmltree::Attribute) -> bool {
+        let mut translate = false;
+        for rule in &self.rules.0 {
+            if let Rule::Translate(s, t) = rule {
+                if s.selects_attribute(owner, attribute) {
+                    translate = *t;
+                }
+            }
+        }
+        translate
+    }
+
+    /// Ends the current section
+    fn flush(&mut self) {
+        let text = std::mem::take(&mut self.current);
+        let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        if !text.is_empty() {
+            self.sections.push(Section { text, key: self.key.clone(), context: self.context.clone(), ..Default::default() });
+        }
+        self.sections.append(&mut self.attributes);
+    }
+
+    fn walk(&mut self, node: Node, translate: bool, loc_note: Option<String>) {
+        for child in node.children() {
+            if child.is_text() {
+                if translate {
+                    self.current.push_str(child.text().unwrap_or(""));
+                }
+                continue;
+            }
+            if !child.is_element() || child.tag_name().namespace() == Some(ITS_NS) {
+                continue;
+            }
+
+            let properties = self.properties(child);
+            let translate = properties.translate.unwrap_or(translate);
+            let loc_note = properties.loc_note.or_else(|| loc_note.clone());
+            for attribute in child.attributes().iter().filter(|a| !a.value().trim().is_empty()) {
+                if self.is_translatable_attribute(child, attribute) {
+                    let key = properties.id.clone().or_else(|| self.key.clone());
+                    self.attributes.push(Section { text: String::from(attribute.value()), key, context: loc_note.clone(), ..Default::default() });
+                }
+            }
+
+            match properties.within_text.unwrap_or(WithinText::No) {
+                WithinText::Yes => self.walk(child, translate, loc_note),
+                WithinText::No => {
+                    self.flush();
+                    let key = std::mem::replace(&mut self.key, properties.id);
+                    let context = std::mem::replace(&mut self.context, loc_note.clone());
+                    self.walk(child, translate, loc_note);
+                    self.flush();
+                    self.key = key;
+                    self.context = context;
+                },
+                // Nested elements, such as footnotes, are separate sections that follow the surrounding text
+                WithinText::Nested => {
+                    let current = std::mem::take(&mut self.current);
+                    let attributes = std::mem::take(&mut self.attributes);
+                    let sections = std::mem::take(&mut self.sections);
+                    let key = std::mem::replace(&mut self.key, properties.id);
+                    let context = std::mem::replace(&mut self.context, loc_note.clone());
+                    self.walk(child, translate, loc_note);
+                    self.flush();
+                    let nested = std::mem::replace(&mut self.sections, sections);
+                    self.current = current;
+                    self.attributes = attributes;
+                    self.attributes.extend(nested);
+                    self.key = key;
+                    self.context = context;
+                },
+            }
+        }
+    }
+}
+
+impl Xml {
+    /// Extracts the sections of a parsed document, also used by rules for XML vocabularies such as DITA
+    pub(crate) fn extract_sections_from_document(&self, doc: &roxmltree::Document) -> Vec<Section> {
+        let mut rules = self.rules.clone();
+        rules.extend(ItsRules::from_document(doc));
+        let mut walker = Walker {
+            rules: &rules,
+            elements_within_text: &self.elements_within_text,
+            sections: Vec::new(),
+            current: String::new(),
+            key: None,
+            context: None,
+            attributes: Vec::new(),
+        };
+        walker.walk(doc.root(), true, None);
+        walker.flush();
+        walker.sections
+    }
+}
 
 impl Extract for Xml {
     fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool {
-        extension == Some("xml") || infer::text::is_xml(buf)
+        extension == Some("xml") || (extension.is_none() && infer::text::is_xml(buf))
     }
 
-    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
-        let str = from_utf8(buf)?;        
-        let doc = roxmltree::Document::parse(str)?;    
-        Ok(extract_text_from_node(doc.root()))
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let str = from_utf8(buf)?;
+        let doc = roxmltree::Document::parse(str)?;
+        Ok(self.extract_sections_from_document(&doc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested_sections_follow_their_parent() {
+        let xml = br#"<doc xmlns:its="http://www.w3.org/2005/11/its" its:version="2.0">
+            <p>Before <fn its:withinText="nested">A note.</fn>after <b its:withinText="yes">bold</b>.</p>
+            <p its:translate="no">Code</p>
+            <p>Next</p>
+        </doc>"#;
+        assert_eq!(Xml::default().extract(xml).unwrap(), vec!["Before after bold.", "A note.", "Next"]);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,202 @@
+use std::error::Error;
+use std::str::from_utf8;
+use crate::extract::{Extract, Section};
+use super::json::matches_pattern;
+use yaml_rust::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust::scanner::{Marker, TScalarStyle};
+
+/// Extraction rule for .yml and .yaml files, such as Rails and Symfony locale files.
+/// Every string value becomes a section keyed by its dotted path (e.g. users.show.title).
+/// Aliases are not extracted, so values shared through anchors are only counted once.
+///
+/// Include and exclude patterns work like the patterns of the [JSON extraction rule](super::json::Json),
+/// path globs can also be written with dots instead of slashes, e.g. `users.*.title`.
+pub struct Yaml {
+    /// Only extract strings that match one of these patterns. Everything is extracted when empty.
+    pub include: Vec<String>,
+
+    /// Never extract strings that match one of these patterns
+    pub exclude: Vec<String>,
+
+    /// Leave out the root key when it is the only key and a locale code, like the `en:` of Rails locale files
+    pub strip_locale_root: bool,
+}
+
+impl Default for Yaml {
+    fn default() -> Self {
+        Yaml { include: Vec::new(), exclude: Vec::new(), strip_locale_root: true }
+    }
+}
+
+enum Frame {
+    Mapping { key: Option<String> },
+    Sequence { index: usize },
+}
+
+/// Collects the string scalars of a document together with their path
+#[derive(Default)]
+struct Collector {
+    frames: Vec<Frame>,
+    root_keys: Vec<String>,
+    values: Vec<(Vec<String>, String)>,
+}
+
+impl Collector {
+    /// Moves the innermost container on to its next key or item
+    fn advance(&mut self) {
+        match self.frames.last_mut() {
+            Some(Frame::Mapping { key }) => *key = None,
+            Some(Frame::Sequence { index }) => *index += 1,
+            None => (),
+        }
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.frames.iter().map(|f| match f {
+            Frame::Mapping { key } => key.clone().unwrap_or_default(),
+            Frame::Sequence { index } => index.to_string(),
+        }).collect()
+    }
+
+    fn expects_key(&self) -> bool {
+        matches!(self.frames.last(), Some(Frame::Mapping { key: None }))
+    }
+}
+
+/// Whether a plain scalar is an int or float of the YAML 1.2 core schema, e.g. 12, -3, 0x1F, 0o17, 1.5e3, .5, -.inf or .nan
+fn is_number(s: &str) -> bool {
+    let digits = |s: &str, radix: u32| !s.is_empty() && s.chars().all(|c| c.is_digit(radix));
+    if let Some(hex) = s.strip_prefix("0x") {
+        return digits(hex, 16);
+    }
+    if let Some(octal) = s.strip_prefix("0o") {
+        return digits(octal, 8);
+    }
+    if matches!(s, ".nan" | ".NaN" | ".NAN") {
+        return true;
+    }
+    let s = s.strip_prefix(['-', '+']).unwrap_or(s);
+    if matches!(s, ".inf" | ".Inf" | ".INF") {
+        return true;
+    }
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((m, e)) => (m, Some(e)),
+        None => (s, None),
+    };
+    let mantissa = match mantissa.split_once('.') {
+        Some((int, fraction)) => (digits(int, 10) && (fraction.is_empty() || digits(fraction, 10))) || (int.is_empty() && digits(fraction, 10)),
+        None => digits(mantissa, 10),
+    };
+    let exponent = match exponent {
+        Some(e) => digits(e.strip_prefix(['-', '+']).unwrap_or(e), 10),
+        None => true,
+    };
+    mantissa && exponent
+}
+
+/// Plain scalars that YAML resolves to null, booleans or numbers
+fn is_plain_non_string(s: &str) -> bool {
+    matches!(s, "~" | "null" | "Null" | "NULL" | "true" | "True" | "TRUE" | "false" | "False" | "FALSE" | "")
+        || is_number(s)
+}
+
+impl MarkedEventReceiver for Collector {
+    fn on_event(&mut self, ev: Event, _mark: Marker) {
+        match ev {
+            Event::Scalar(value, _, _, _) if self.expects_key() => {
+                if self.frames.len() == 1 {
+                    self.root_keys.push(value.clone());
+                }
+                if let Some(Frame::Mapping { key }) = self.frames.last_mut() {
+                    *key = Some(value);
+                }
+            },
+            Event::Scalar(value, style, _, _) => {
+                if style != TScalarStyle::Plain || !is_plain_non_string(&value) {
+                    self.values.push((self.path(), value));
+                }
+                self.advance();
+            },
+            Event::Alias(_) => {
+                if self.expects_key() {
+                    if let Some(Frame::Mapping { key }) = self.frames.last_mut() {
+                        *key = Some(String::from("*"));
+                    }
+                } else {
+                    self.advance();
+                }
+            },
+            Event::MappingStart(_) => self.frames.push(Frame::Mapping { key: None }),
+            Event::SequenceStart(_) => self.frames.push(Frame::Sequence { index: 0 }),
+            Event::MappingEnd | Event::SequenceEnd => {
+                self.frames.pop();
+                self.advance();
+            },
+            _ => (),
+        }
+    }
+}
+
+/// Whether a key looks like a locale code, e.g. en, pt-BR or zh_Hans
+fn is_locale(key: &str) -> bool {
+    let (language, region) = match key.split_once(['-', '_']) {
+        Some((l, r)) => (l, Some(r)),
+        None => (key, None),
+    };
+    let valid_region = match region {
+        Some(r) => (2..=4).contains(&r.len()) && r.chars().all(|c| c.is_ascii_alphanumeric()),
+        None => true,
+    };
+    (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase()) && valid_region
+}
+
+impl Yaml {
+    fn matches(&self, pattern: &str, path: &[String]) -> bool {
+        if pattern.starts_with('$') || pattern.contains('/') || !pattern.contains('.') {
+            return matches_pattern(pattern, path);
+        }
+        matches_pattern(&pattern.replace('.', "/"), path)
+    }
+
+    fn is_wanted(&self, path: &[String]) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| self.matches(p, path)))
+            && !self.exclude.iter().any(|p| self.matches(p, path))
+    }
+}
+
+impl Extract for Yaml {
+    fn can_extract(&self, _buf: &[u8], extension: Option<&str>) -> bool {
+        extension == Some("yml") || extension == Some("yaml")
+    }
+
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        let s = from_utf8(buf)?;
+        let mut collector = Collector::default();
+        Parser::new(s.chars()).load(&mut collector, true)?;
+
+        let strip = self.strip_locale_root && collector.root_keys.len() == 1 && is_locale(&collector.root_keys[0]);
+        let mut vec = Vec::new();
+        for (mut path, text) in collector.values {
+            if strip && !path.is_empty() {
+                path.remove(0);
+            }
+            if self.is_wanted(&path) {
+                vec.push(Section { text, key: Some(path.join(".")), ..Default::default() });
+            }
+        }
+        Ok(vec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_only_yaml_numbers() {
+        let yaml = "en:\n  nan: Nan\n  infinity: Infinity\n  inf: inf\n  version: 1.0.0\n  count: 3\n  ratio: -1.5e3\n  half: .5\n  hex: 0x1F\n  octal: 0o17\n  limit: -.inf\n  missing: .nan\n  quoted: \"42\"\n";
+        let sections = Yaml::default().extract_sections(yaml.as_bytes()).unwrap();
+        let sections: Vec<(&str, &str)> = sections.iter().map(|s| (s.key.as_deref().unwrap(), s.text.as_str())).collect();
+        assert_eq!(sections, vec![("nan", "Nan"), ("infinity", "Infinity"), ("inf", "inf"), ("version", "1.0.0"), ("quoted", "42")]);
+    }
+}
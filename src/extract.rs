@@ -3,12 +3,13 @@ use std::error::Error;
 use std::fmt;
 use std::path::Path;
 use std::ffi::OsStr;
+use serde::{Serialize, Deserialize};
 
-use crate::default_extractors::{txt,xml,docx,json,pptx,xlsx,xliff,html};
+use crate::default_extractors::{txt,xml,docx,json,pptx,xlsx,xliff,html,pdf,odf,markdown,i18next,chrome,arb,yaml,android,apple,resx,properties,qt,fluent,sdlxliff,mqxliff,dita,docbook,idml,epub,latex};
 
 /// Thrown when parsing a file fails
 #[derive(Debug)]
-pub struct ExtractionError(String);
+pub struct ExtractionError(pub(crate) String);
 
 impl fmt::Display for ExtractionError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -18,13 +19,49 @@ impl fmt::Display for ExtractionError {
 
 impl Error for ExtractionError {}
 
+/// A section of translatable text together with where it was found in the file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Section {
+    /// The translatable text
+    pub text: String,
+
+    /// Identifies the section within the file, e.g. the JSON pointer of a value
+    pub key: Option<String>,
+
+    /// Information for the translator, such as the description of a message or the plural form of a variant
+    pub context: Option<String>,
+
+    /// The part of the file this section belongs to, if it should be reported separately in the [analysis](crate::analysis::Analysis). E.g. speaker notes.
+    pub part: Option<String>,
+
+    /// Content the rule could not extract, which is listed in the warnings of the [analysis](crate::analysis::Analysis). E.g. PDF pages without a text layer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+}
+
+impl From<String> for Section {
+    fn from(text: String) -> Self {
+        Section { text, ..Default::default() }
+    }
+}
+
 /// This trait is used to define extraction rule structs
 pub trait Extract {
-    /// Defines whether this rule can actually extract a file given the content and extension
+    /// Defines whether this rule can actually extract a file given the content and extension.
+    /// The extension is None when the file has none or when no rule accepted it, only then should a rule recognize a file by its content alone.
     fn can_extract(&self, buf: &[u8], extension: Option<&str>) -> bool;
 
     /// The extraction logic, parses the file and extracts sections of translatable text.
-    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>>;
+    /// Rules implement at least one of extract and [extract_sections](Extract::extract_sections), this defaults to the texts of the sections.
+    fn extract(&self, buf: &[u8]) -> Result<Vec<String>, Box<dyn Error>> {
+        Ok(self.extract_sections(buf)?.into_iter().map(|s| s.text).collect())
+    }
+
+    /// Like [extract](Extract::extract), but keeps track of where each section came from.
+    /// Rules that know more about the structure of a file than plain text override this.
+    fn extract_sections(&self, buf: &[u8]) -> Result<Vec<Section>, Box<dyn Error>> {
+        Ok(self.extract(buf)?.into_iter().map(Section::from).collect())
+    }
 }
 
 /// Wrapper around implementations of [Extract](Extract) trait. Add custom extraction rules or use the [default extraction rules](ExtractionRules).
@@ -39,6 +76,17 @@ impl ExtractionRules {
         self.rules.push(rule);
     }
 
+    /// The first rule that accepts the file with its extension. When no rule does, the rules are asked again without the extension,
+    /// so that a file with an unknown extension can still be recognized by its content.
+    fn find(&self, buf: &[u8], extension: Option<&str>) -> Option<&dyn Extract> {
+        let rules = || self.rules.iter().map(|r| r.as_ref());
+        match rules().find(|r| r.can_extract(buf, extension)) {
+            Some(rule) => Some(rule),
+            None if extension.is_some() => rules().find(|r| r.can_extract(buf, None)),
+            None => None,
+        }
+    }
+
     /// Instantiate a new [ExtractionRules](ExtractionRules) set
     pub fn new() -> Self {
         Self {
@@ -52,13 +100,37 @@ impl Default for ExtractionRules {
     fn default() -> Self {
         let mut new = ExtractionRules::new();
         new.add(Box::new(xliff::Xliff));
+        new.add(Box::new(sdlxliff::Sdlxliff));
+        new.add(Box::new(mqxliff::Mqxliff));
         new.add(Box::new(txt::Txt));
-        new.add(Box::new(xml::Xml));
-        new.add(Box::new(docx::Docx));
-        new.add(Box::new(json::Json));
-        new.add(Box::new(pptx::Pptx));
-        new.add(Box::new(xlsx::Xlsx));
+        new.add(Box::new(android::AndroidStrings));
+        new.add(Box::new(apple::AppleStrings));
+        new.add(Box::new(apple::StringsDict));
+        new.add(Box::new(apple::StringCatalog::default()));
+        new.add(Box::new(resx::Resx));
+        new.add(Box::new(properties::Properties));
+        new.add(Box::new(qt::QtLinguist));
+        new.add(Box::new(fluent::Fluent));
+        new.add(Box::new(dita::Dita::default()));
+        new.add(Box::new(docbook::DocBook::default()));
         new.add(Box::new(html::Html));
+        new.add(Box::new(xml::Xml::default()));
+        new.add(Box::new(docx::Docx::default()));
+        new.add(Box::new(chrome::ChromeMessages));
+        new.add(Box::new(i18next::I18next::default()));
+        new.add(Box::new(json::Json::default()));
+        new.add(Box::new(arb::Arb));
+        new.add(Box::new(yaml::Yaml::default()));
+        new.add(Box::new(pptx::Pptx::default()));
+        new.add(Box::new(idml::Idml::default()));
+        new.add(Box::new(epub::Epub::default()));
+        new.add(Box::new(xlsx::Xlsx::default()));
+        new.add(Box::new(pdf::Pdf::default()));
+        new.add(Box::new(odf::Odt::default()));
+        new.add(Box::new(odf::Ods::default()));
+        new.add(Box::new(odf::Odp::default()));
+        new.add(Box::new(markdown::Markdown::default()));
+        new.add(Box::new(latex::Latex));
         new
     }
 }
@@ -82,10 +154,50 @@ impl Default for ExtractionRules {
 /// 
 pub fn extract(buf: Vec<u8>, path: &str, rules: ExtractionRules) -> Result<Vec<String>, Box<dyn Error>> {
     let extension = Path::new(path).extension().and_then(OsStr::to_str);
-    for rule in rules.rules {
-        if rule.can_extract(&buf, extension) {
-            return rule.extract(&buf)
-        }
+    match rules.find(&buf, extension) {
+        Some(rule) => rule.extract(&buf),
+        None => Err(Box::new(ExtractionError(String::from("No rule matched file type")))),
     }
-    Err(Box::new(ExtractionError(String::from("No rule matched file type"))))
-}
\ No newline at end of file
+}
+
+/// Like [extract](extract), but returns [sections](Section) that keep track of the part of the file they were found in.
+/// Use together with [hashment_sections](crate::segmentation::hashment_sections) to get a per part breakdown in the analysis.
+/// # Errors
+/// [ExtractionError](ExtractionError): No rule matched the file and/or path
+pub fn extract_sections(buf: Vec<u8>, path: &str, rules: ExtractionRules) -> Result<Vec<Section>, Box<dyn Error>> {
+    let extension = Path::new(path).extension().and_then(OsStr::to_str);
+    match rules.find(&buf, extension) {
+        Some(rule) => rule.extract_sections(&buf),
+        None => Err(Box::new(ExtractionError(String::from("No rule matched file type")))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_starting_with_html_is_extracted_as_markdown() {
+        let buf = b"<!-- intro -->\n# Title\n\n```\nfn main() {}\n```\n\n<p align=\"center\">Centered</p>\n\nSome text.".to_vec();
+        let texts = extract(buf, "README.md", ExtractionRules::default()).unwrap();
+        assert!(texts.contains(&String::from("Title")));
+        assert!(texts.contains(&String::from("Some text.")));
+        assert!(!texts.iter().any(|t| t.contains("fn main")));
+    }
+
+    #[test]
+    fn xml_starting_with_comment_is_extracted_as_xml() {
+        let buf = br#"<!-- c --><doc xmlns:its="http://www.w3.org/2005/11/its">
+            <its:rules version="2.0"><its:translateRule selector="//code" translate="no"/></its:rules>
+            <p><code>let x = 1;</code> Hi there</p></doc>"#.to_vec();
+        let texts = extract(buf, "a.xml", ExtractionRules::default()).unwrap();
+        assert_eq!(texts, vec!["Hi there"]);
+    }
+
+    #[test]
+    fn unknown_extension_is_recognized_by_content() {
+        let buf = b"<!DOCTYPE html><html><body><p>Hello</p></body></html>".to_vec();
+        let texts = extract(buf, "page.tpl", ExtractionRules::default()).unwrap();
+        assert_eq!(texts, vec!["Hello"]);
+    }
+}
@@ -0,0 +1,193 @@
+/// The maximum amount of variants a single message is expanded into, to keep deeply nested selects from exploding
+const MAX_VARIANTS: usize = 64;
+
+pub(crate) enum Part {
+    Text(String),
+    /// The branches of a plural, selectordinal or select argument
+    Choice(Vec<Vec<Part>>),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    /// Reads until one of the stop characters, without consuming it
+    fn read_until(&mut self, stop: &[char]) -> String {
+        let mut s = String::new();
+        while let Some(c) = self.peek() {
+            if stop.contains(&c) {
+                break;
+            }
+            s.push(c);
+            self.pos += 1;
+        }
+        s
+    }
+
+    /// Parses message text up to an unmatched closing brace or the end of the input
+    fn message(&mut self, in_plural: bool) -> Vec<Part> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            match c {
+                '}' => break,
+                '{' => {
+                    self.pos += 1;
+                    parts.push(Part::Text(std::mem::take(&mut text)));
+                    if let Some(choice) = self.argument() {
+                        parts.push(choice);
+                    }
+                },
+                '\'' => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some('\'') => {
+                            text.push('\'');
+                            self.pos += 1;
+                        },
+                        // Quoted literal text, e.g. '{' or '#'
+                        Some('{') | Some('}') => {
+                            text += &self.read_until(&['\'']);
+                            self.pos += 1;
+                        },
+                        Some('#') if in_plural => {
+                            text += &self.read_until(&['\'']);
+                            self.pos += 1;
+                        },
+                        _ => text.push('\''),
+                    }
+                },
+                // The number of a plural is a placeholder
+                '#' if in_plural => self.pos += 1,
+                c => {
+                    text.push(c);
+                    self.pos += 1;
+                },
+            }
+        }
+        parts.push(Part::Text(text));
+        parts
+    }
+
+    /// Parses an argument after its opening brace, up to and including its closing brace.
+    /// Simple arguments ({name}, {n, number}) are placeholders and return nothing.
+    fn argument(&mut self) -> Option<Part> {
+        self.read_until(&[',', '}']);
+        if self.peek() == Some(',') {
+            self.pos += 1;
+        }
+        let kind = self.read_until(&[',', '}']);
+        let kind = kind.trim();
+        if self.peek() == Some(',') && matches!(kind, "plural" | "selectordinal" | "select") {
+            self.pos += 1;
+            let mut branches = Vec::new();
+            loop {
+                self.skip_whitespace();
+                match self.peek() {
+                    Some('{') => {
+                        self.pos += 1;
+                        branches.push(self.message(kind != "select"));
+                        self.pos += 1;
+                    },
+                    Some('}') | None => break,
+                    // Selectors like one, other, =0 and offset:1
+                    Some(_) => {
+                        self.read_until(&['{', '}', ' ', '\t', '\n']);
+                    },
+                }
+            }
+            self.pos += 1;
+            return Some(Part::Choice(branches));
+        }
+
+        // Skip the style of a simple argument, which can contain nested braces in skeletons
+        let mut depth = 0;
+        while let Some(c) = self.peek() {
+            self.pos += 1;
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => break,
+                '}' => depth -= 1,
+                _ => (),
+            }
+        }
+        None
+    }
+}
+
+/// Every combination of the branches of the choices in parts, with the surrounding text included
+pub(crate) fn variants(parts: &[Part]) -> Vec<String> {
+    let mut vec = vec![String::new()];
+    for part in parts {
+        match part {
+            Part::Text(t) => vec.iter_mut().for_each(|v| v.push_str(t)),
+            Part::Choice(branches) => {
+                let mut expanded = Vec::new();
+                for v in &vec {
+                    for branch in branches {
+                        for b in variants(branch) {
+                            expanded.push(format!("{}{}", v, b));
+                        }
+                    }
+                }
+                expanded.truncate(MAX_VARIANTS);
+                vec = expanded;
+            },
+        }
+    }
+    vec
+}
+
+/// Expands an ICU MessageFormat message into the texts a translator works on: one variant for every plural or select branch,
+/// with the surrounding text included and placeholders such as {name} and the # of plurals left out.
+/// A message without plural or select arguments returns a single variant.
+pub fn expand(message: &str) -> Vec<String> {
+    let mut parser = Parser { chars: message.chars().collect(), pos: 0 };
+    let mut parts = Vec::new();
+    while parser.pos < parser.chars.len() {
+        parts.append(&mut parser.message(false));
+        // Stray closing brace
+        parser.pos += 1;
+    }
+    let mut vec = variants(&parts);
+    vec.dedup();
+    vec
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_plural_and_select() {
+        assert_eq!(expand("Hello {name}!"), vec!["Hello !"]);
+        assert_eq!(expand("{n, plural, one {# file} other {# files}} selected"), vec![" file selected", " files selected"]);
+        assert_eq!(expand("{n, plural, offset:1 =0 {Nobody} one {{name}} other {{name} and # others}}"), vec!["Nobody", "", " and  others"]);
+    }
+
+    #[test]
+    fn expands_nested_choices() {
+        let message = "{gender, select, female {{n, plural, one {She has # cat} other {She has # cats}}} other {{n, plural, one {They have # cat} other {They have # cats}}}}";
+        assert_eq!(expand(message), vec!["She has  cat", "She has  cats", "They have  cat", "They have  cats"]);
+        assert_eq!(expand("{a, select, x {{b, select, y {#1} other {#2}}} other {none}}"), vec!["#1", "#2", "none"]);
+    }
+
+    #[test]
+    fn keeps_quoted_text() {
+        assert_eq!(expand("It''s '{name}' day"), vec!["It's {name} day"]);
+        assert_eq!(expand("{n, plural, other {'#' # of them}}"), vec!["#  of them"]);
+        assert_eq!(expand("Don't {n, number, ::currency/EUR}"), vec!["Don't "]);
+    }
+}
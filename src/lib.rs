@@ -2,10 +2,13 @@ mod hash;
 
 mod zip_extensions;
 mod xml_extensions;
+mod icu;
+mod printf;
 
 pub mod memory;
 pub mod analysis;
 pub mod segmentation;
 pub mod unicode;
 pub mod extract;
+pub mod progress;
 pub mod default_extractors;
\ No newline at end of file
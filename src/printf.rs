@@ -0,0 +1,79 @@
+const LENGTH_MODIFIERS: [&str; 9] = ["hh", "ll", "h", "l", "q", "z", "t", "j", "L"];
+const CONVERSIONS: &str = "@dDiuUxXoOfFeEgGcCsSaAp";
+
+/// The length of the format specifier at the start of s, which starts right after the %
+fn specifier_length(s: &str) -> Option<usize> {
+    // Apple's %#@name@ variables of .stringsdict files and String Catalogs
+    if let Some(rest) = s.strip_prefix("#@") {
+        return rest.find('@').map(|end| end + 3);
+    }
+    let bytes = s.as_bytes();
+    let mut i = 0;
+    let skip_digits = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    // Positional argument, e.g. %1$s
+    let digits = skip_digits(i);
+    if digits > i && bytes.get(digits) == Some(&b'$') {
+        i = digits + 1;
+    }
+    // The space flag is left out, so that a literal % before a word ("50% off") is not read as a placeholder
+    while i < bytes.len() && b"-+0#'".contains(&bytes[i]) {
+        i += 1;
+    }
+    i = skip_digits(i);
+    if bytes.get(i) == Some(&b'.') {
+        i = skip_digits(i + 1);
+    }
+    if let Some(modifier) = LENGTH_MODIFIERS.iter().find(|m| s[i..].starts_with(*m)) {
+        i += modifier.len();
+    }
+    match bytes.get(i) {
+        Some(c) if CONVERSIONS.as_bytes().contains(c) => Some(i + 1),
+        _ => None,
+    }
+}
+
+/// Removes printf style placeholders such as %s, %1$d, %@ and %lld, which are not translated, and unescapes %%.
+/// A % that does not start a valid placeholder is kept.
+pub fn strip_placeholders(s: &str) -> String {
+    let mut result = String::new();
+    let mut rest = s;
+    while let Some(start) = rest.find('%') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        rest = if let Some(after) = after.strip_prefix('%') {
+            result.push('%');
+            after
+        } else if let Some(length) = specifier_length(after) {
+            &after[length..]
+        } else {
+            result.push('%');
+            after
+        };
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_placeholders() {
+        assert_eq!(strip_placeholders("Hello %@, you have %1$d new %lld messages"), "Hello , you have  new  messages");
+        assert_eq!(strip_placeholders("%#@files@ selected"), " selected");
+        assert_eq!(strip_placeholders("%-5s|%05.2f"), "|");
+    }
+
+    #[test]
+    fn keeps_literal_percent_signs() {
+        assert_eq!(strip_placeholders("100% sure, 50% off"), "100% sure, 50% off");
+        assert_eq!(strip_placeholders("Save 20%!"), "Save 20%!");
+        assert_eq!(strip_placeholders("100%% done"), "100% done");
+    }
+}
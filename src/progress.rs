@@ -0,0 +1,261 @@
+use std::error::Error;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::ffi::OsStr;
+use serde::{Serialize, Deserialize};
+use crate::{analysis::{Analysis, Counts}, extract::ExtractionError, memory::HashedMemory, segmentation::{hashment, Hashment, SegmentationRules}};
+use crate::default_extractors::{xliff, sdlxliff, mqxliff};
+
+/// The progress of a segment in a bilingual file. The states of the different formats are mapped onto these.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TranslationState {
+    /// There is no translation yet
+    New,
+
+    /// There is a translation, but it has to be redone, e.g. because the source changed
+    NeedsTranslation,
+
+    /// Translated, possibly waiting for review
+    Translated,
+
+    /// Translated and reviewed
+    Reviewed,
+
+    /// Final or signed off, no more changes are expected
+    Final,
+}
+
+impl TranslationState {
+    /// Whether the segment still has to be translated
+    pub fn is_pending(&self) -> bool {
+        *self <= TranslationState::NeedsTranslation
+    }
+}
+
+/// A segment of a bilingual file with its translation and the metadata that is relevant for progress
+#[derive(Debug, Clone)]
+pub struct Unit {
+    /// Identifies the segment within the file
+    pub id: Option<String>,
+
+    pub source: String,
+
+    pub target: Option<String>,
+
+    pub state: TranslationState,
+
+    /// Where the translation came from, as far as the file says, e.g. exact-match or leveraged-mt
+    pub origin: Option<String>,
+
+    /// The percentage of the translation memory match the translation is based on. 101 is a context match.
+    pub match_percent: Option<u32>,
+
+    /// Locked segments can not be changed by the translator
+    pub locked: bool,
+
+    /// Notes for the translator
+    pub note: Option<String>,
+}
+
+/// This trait is used to define rules that read the segments of bilingual files
+pub trait Bilingual {
+    /// Defines whether this rule can read a file given the content and extension
+    fn can_read(&self, buf: &[u8], extension: Option<&str>) -> bool;
+
+    /// Reads all segments of the file, including the translated ones
+    fn units(&self, buf: &[u8]) -> Result<Vec<Unit>, Box<dyn Error>>;
+}
+
+/// Wrapper around implementations of the [Bilingual](Bilingual) trait. Add custom rules or use the [default rules](BilingualRules) for XLIFF, SDLXLIFF and MQXLIFF.
+pub struct BilingualRules {
+    rules: Vec<Box<dyn Bilingual>>,
+}
+
+impl BilingualRules {
+    /// Add a [Bilingual](Bilingual) implementation to the ruleset
+    pub fn add(&mut self, rule: Box<dyn Bilingual>) {
+        self.rules.push(rule);
+    }
+
+    /// Instantiate a new [BilingualRules](BilingualRules) set
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new()
+        }
+    }
+}
+
+/// The default implementation of [BilingualRules](BilingualRules) uses the bilingual rules in the default_extractors folder
+impl Default for BilingualRules {
+    fn default() -> Self {
+        let mut new = BilingualRules::new();
+        new.add(Box::new(xliff::Xliff));
+        new.add(Box::new(sdlxliff::Sdlxliff));
+        new.add(Box::new(mqxliff::Mqxliff));
+        new
+    }
+}
+
+/// The [analysis](Analysis) of a bilingual file, broken down by the state, origin and match percentage of the translations.
+/// Repetitions are counted over the whole file, so a segment that repeats a segment in another state is a repetition in its own state.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Progress {
+    /// The analysis of all source segments, translated or not
+    pub total: Analysis,
+
+    /// The analysis per [state](TranslationState)
+    pub states: BTreeMap<TranslationState, Analysis>,
+
+    /// The analysis per origin of the translation. Segments without origin are not included.
+    pub origins: BTreeMap<String, Analysis>,
+
+    /// The analysis per match band, e.g. "100%" or "85-94%". Segments without match percentage are not included.
+    pub matches: BTreeMap<String, Analysis>,
+
+    /// The analysis of locked segments
+    pub locked: Analysis,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            total: Analysis::new(),
+            states: BTreeMap::new(),
+            origins: BTreeMap::new(),
+            matches: BTreeMap::new(),
+            locked: Analysis::new(),
+        }
+    }
+}
+
+/// Adds the counts of a segment to an analysis
+fn add(analysis: &mut Analysis, hashment: &Hashment, repetition: bool, matched: bool) {
+    analysis.total += Counts::from(hashment);
+    if repetition {
+        analysis.repetitions += Counts::from(hashment);
+    }
+    if matched {
+        analysis.matches += Counts::from(hashment);
+    }
+}
+
+/// The match band of a match percentage, as used in the analysis reports of CAT tools
+fn match_band(percent: u32) -> &'static str {
+    match percent {
+        101.. => "101%",
+        100 => "100%",
+        95..=99 => "95-99%",
+        85..=94 => "85-94%",
+        75..=84 => "75-84%",
+        50..=74 => "50-74%",
+        _ => "no match",
+    }
+}
+
+/// Breaks the analysis of the source text of [units](Unit) down by their state, origin and match percentage,
+/// given an (optional) translation memory
+pub fn progress<T: SegmentationRules>(units: &[Unit], rules: &T, memory: &HashedMemory) -> Progress {
+    let mut progress = Progress::default();
+    let mut repetition_memory = HashedMemory::new();
+    for unit in units {
+        for hashment in hashment(&unit.source, rules) {
+            let repetition = repetition_memory.contains_hash(&hashment.hash);
+            let matched = memory.contains_hash(&hashment.hash);
+            repetition_memory.add_hash(hashment.hash);
+
+            add(&mut progress.total, &hashment, repetition, matched);
+            add(progress.states.entry(unit.state).or_insert_with(Analysis::new), &hashment, repetition, matched);
+            if let Some(origin) = &unit.origin {
+                add(progress.origins.entry(origin.clone()).or_insert_with(Analysis::new), &hashment, repetition, matched);
+            }
+            if let Some(percent) = unit.match_percent {
+                add(progress.matches.entry(String::from(match_band(percent))).or_insert_with(Analysis::new), &hashment, repetition, matched);
+            }
+            if unit.locked {
+                add(&mut progress.locked, &hashment, repetition, matched);
+            }
+        }
+    }
+    progress
+}
+
+/// Reads a bilingual file, such as XLIFF, with the first of the [bilingual rules](BilingualRules) that can read it
+/// and reports how far along its translation is.
+/// # Examples
+/// ```no_run
+/// use filecount::memory::HashedMemory;
+/// use filecount::progress::{analyze_progress, BilingualRules};
+/// use filecount::unicode::UnicodeRules;
+///
+/// let buf = std::fs::read("files/vendor.xlf").unwrap();
+/// let progress = analyze_progress(&buf, "files/vendor.xlf", BilingualRules::default(), &UnicodeRules, &HashedMemory::new()).unwrap();
+/// println!("{:?}", progress.states);
+/// ```
+/// # Errors
+/// [ExtractionError](ExtractionError): No rule matched the file type
+pub fn analyze_progress<T: SegmentationRules>(buf: &[u8], path: &str, readers: BilingualRules, rules: &T, memory: &HashedMemory) -> Result<Progress, Box<dyn Error>> {
+    let extension = Path::new(path).extension().and_then(OsStr::to_str);
+    for reader in readers.rules {
+        if reader.can_read(buf, extension) {
+            return Ok(progress(&reader.units(buf)?, rules, memory));
+        }
+    }
+    Err(Box::new(ExtractionError(String::from("No bilingual rule matched file type"))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unicode::UnicodeRules;
+
+    const XLIFF: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file source-language="en" target-language="de" datatype="plaintext" original="app">
+    <body>
+      <trans-unit id="1"><source>Hello world.</source></trans-unit>
+      <trans-unit id="2">
+        <source>Hello world.</source>
+        <target state="translated" state-qualifier="exact-match">Hallo Welt.</target>
+        <alt-trans match-quality="100"><source>Hello world.</source><target>Hallo Welt.</target></alt-trans>
+      </trans-unit>
+      <trans-unit id="3">
+        <source>Save the file.</source>
+        <target state="final">Speichern Sie die Datei.</target>
+        <alt-trans match-quality="87%"><source>Save a file.</source><target>Eine Datei speichern.</target></alt-trans>
+      </trans-unit>
+      <trans-unit id="4">
+        <source>Open the door.</source>
+        <target state="needs-translation" state-qualifier="mt-suggestion">Öffne die Tür.</target>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>"#;
+
+    fn words(analysis: Option<&Analysis>) -> (usize, usize, usize) {
+        analysis.map(|a| (a.total.words, a.repetitions.words, a.matches.words)).unwrap_or_default()
+    }
+
+    #[test]
+    fn breaks_xliff_down_by_state_origin_and_match() {
+        let mut memory = HashedMemory::new();
+        memory.add("Open the door.");
+        let progress = analyze_progress(XLIFF.as_bytes(), "vendor.xlf", BilingualRules::default(), &UnicodeRules, &memory).unwrap();
+
+        assert_eq!(words(Some(&progress.total)), (10, 2, 3));
+        assert_eq!(words(progress.states.get(&TranslationState::New)), (2, 0, 0));
+        assert_eq!(words(progress.states.get(&TranslationState::Translated)), (2, 2, 0));
+        assert_eq!(words(progress.states.get(&TranslationState::NeedsTranslation)), (3, 0, 3));
+        assert_eq!(words(progress.states.get(&TranslationState::Final)), (3, 0, 0));
+        assert_eq!(words(progress.origins.get("exact-match")), (2, 2, 0));
+        assert_eq!(words(progress.origins.get("mt-suggestion")), (3, 0, 3));
+        assert_eq!(words(progress.matches.get("100%")), (2, 2, 0));
+        assert_eq!(words(progress.matches.get("85-94%")), (3, 0, 0));
+        assert_eq!(progress.total.total.segments, 4);
+    }
+
+    #[test]
+    fn fails_without_matching_reader() {
+        assert!(analyze_progress(XLIFF.as_bytes(), "vendor.xlf", BilingualRules::new(), &UnicodeRules, &HashedMemory::new()).is_err());
+    }
+}
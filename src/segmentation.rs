@@ -1,4 +1,5 @@
 use crate::hash::hash;
+use crate::extract::Section;
 use serde::{Serialize, Deserialize};
 
 /// A hashment (hashed segment) represents the relevant information of an analyzed segment.
@@ -8,6 +9,15 @@ pub struct Hashment {
     pub hash: u64,
     pub words: usize,
     pub characters: usize,
+
+    /// The part of the file the segment was extracted from, see [Section](Section)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub part: Option<String>,
+
+    /// A warning of the section the segment was extracted from, see [Section](Section).
+    /// Warnings without text are kept as hashments without words, which are not counted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
 }
 
 /// Trait to define custom segmentation rules.
@@ -47,6 +57,8 @@ pub fn hashment<T: SegmentationRules>(section: &str, rules: &T) -> Vec<Hashment>
             hash: hash(segment),
             words: word_count,
             characters: charachter_count,
+            part: None,
+            warning: None,
         })
     }
     
@@ -58,4 +70,25 @@ pub fn hashment<T: SegmentationRules>(section: &str, rules: &T) -> Vec<Hashment>
 /// Define and inject your own segmentation rules to modify the segmentation behaviour.
 pub fn hashment_many<T: SegmentationRules>(sections: Vec<String>, rules: &T) -> Vec<Hashment> {
     sections.iter().flat_map(|s| hashment(s, rules)).collect()
-}
\ No newline at end of file
+}
+
+/// Hashments [sections](Section) while keeping track of the part of the file each segment belongs to and of the warnings of the sections.
+/// Often used in conjunction with extract_sections().
+pub fn hashment_sections<T: SegmentationRules>(sections: Vec<Section>, rules: &T) -> Vec<Hashment> {
+    let mut hashments = Vec::new();
+    for section in sections {
+        let mut segments = hashment(&section.text, rules);
+        match segments.first_mut() {
+            Some(first) => first.warning = section.warning,
+            None if section.warning.is_some() => {
+                hashments.push(Hashment { hash: 0, words: 0, characters: 0, part: None, warning: section.warning });
+            },
+            None => (),
+        }
+        for mut hashment in segments {
+            hashment.part = section.part.clone();
+            hashments.push(hashment);
+        }
+    }
+    hashments
+}
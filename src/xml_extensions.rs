@@ -1,14 +1,15 @@
-pub fn extract_text_from_node(node: roxmltree::Node) -> Vec<String> {
-    let mut vec = Vec::new();                
-    for node in node.descendants().filter(|n| n.is_text()) {
-        match node.text() {
-            Some(t) => vec.push(String::from(t)),
-            None => (),
+/// Extracts the paragraphs (a:p) and cached chart strings (c:v in c:strCache) of a DrawingML part, such as a chart or SmartArt diagram
+pub fn extract_drawing_paragraphs(node: roxmltree::Node) -> Vec<String> {
+    let mut vec = Vec::new();
+    for node in node.descendants() {
+        if node.has_tag_name("p") {
+            let s: String = node.descendants().filter(|n| n.has_tag_name("t")).filter_map(|n| n.text()).collect();
+            vec.push(s);
+        } else if node.has_tag_name("v") && node.ancestors().any(|n| n.has_tag_name("strCache")) {
+            if let Some(t) = node.text() {
+                vec.push(String::from(t));
+            }
         }
     }
     vec
 }
-
-pub fn extract_text_from_nodes(nodes: Vec<roxmltree::Node>) -> Vec<String> {
-    nodes.iter().flat_map(|n| extract_text_from_node(*n)).collect()
-}
\ No newline at end of file
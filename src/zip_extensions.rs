@@ -10,19 +10,102 @@ pub fn read_file_from_zip(buf: &[u8], name: &str) -> Result<String, Box<dyn Erro
     Ok(content)
 }
 
+/// Reads every file whose path contains the given name, ordered by path with numbers compared by value. Relationship (.rels) files are skipped.
 pub fn read_files_from_zip(buf: &[u8], name: &str) -> Result<Vec<String>, Box<dyn Error>> {
     let mut vec = Vec::new();
     let mut zip = zip::ZipArchive::new(Cursor::new(buf))?;
     for i in 0..zip.len() {
         let mut file = zip.by_index(i)?;
-        let file_name = file.name();            
-        if !file_name.contains(name) {
+        let file_name = String::from(file.name());            
+        if !file_name.contains(name) || file_name.ends_with(".rels") {
             continue;
         }
         let mut ciphertext = Vec::new();
         file.read_to_end(&mut ciphertext)?;
         let file_contents = String::from_utf8(ciphertext)?;
-        vec.push(file_contents);
+        vec.push((natural_key(&file_name), file_contents));
+    }
+    vec.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(vec.into_iter().map(|(_, contents)| contents).collect())
+}
+
+/// Splits a name into text and number runs, so that slide2.xml is ordered before slide10.xml
+fn natural_key(name: &str) -> Vec<(String, u64)> {
+    let mut key = Vec::new();
+    let mut text = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() {
+            let mut number = c.to_digit(10).unwrap_or(0) as u64;
+            while let Some(d) = chars.peek().and_then(|d| d.to_digit(10)) {
+                number = number.saturating_mul(10).saturating_add(d as u64);
+                chars.next();
+            }
+            key.push((std::mem::take(&mut text), number));
+        } else {
+            text.push(c);
+        }
+    }
+    key.push((text, 0));
+    key
+}
+
+/// Resolves a (relative) path found inside a part against the folder of that part. Paths starting with / are relative to the root of the archive.
+pub fn resolve_path(base: &str, target: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    if !target.starts_with('/') {
+        segments.extend(base.split('/').filter(|s| !s.is_empty()));
+    }
+    for segment in target.split('/') {
+        match segment {
+            "" | "." => (),
+            ".." => {
+                segments.pop();
+            },
+            s => segments.push(s),
+        }
+    }
+    segments.join("/")
+}
+
+/// A relationship from an Office Open XML part to another part
+pub struct Relationship {
+    pub id: String,
+    pub kind: String,
+    /// The path of the target part within the archive
+    pub target: String,
+}
+
+/// Reads the relationships of an Office Open XML part (e.g. ppt/slides/_rels/slide1.xml.rels for ppt/slides/slide1.xml).
+/// External relationships are left out and a part without relationships returns an empty vector.
+pub fn read_relationships(buf: &[u8], part: &str) -> Result<Vec<Relationship>, Box<dyn Error>> {
+    let (folder, file) = part.rsplit_once('/').unwrap_or(("", part));
+    let rels = match read_file_from_zip(buf, format!("{}/_rels/{}.rels", folder, file).trim_start_matches('/')) {
+        Ok(r) => r,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let doc = roxmltree::Document::parse(&rels)?;
+    let mut vec = Vec::new();
+    for node in doc.descendants().filter(|n| n.has_tag_name("Relationship") && n.attribute("TargetMode") != Some("External")) {
+        if let (Some(id), Some(kind), Some(target)) = (node.attribute("Id"), node.attribute("Type"), node.attribute("Target")) {
+            vec.push(Relationship {
+                id: String::from(id),
+                kind: String::from(kind),
+                target: resolve_path(folder, target),
+            });
+        }
     }
     Ok(vec)
-}
\ No newline at end of file
+}
+
+/// Builds an archive with the given files, for testing rules that read zipped formats
+#[cfg(test)]
+pub(crate) fn build_zip(files: &[(&str, &str)]) -> Vec<u8> {
+    use std::io::Write;
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, content) in files {
+        zip.start_file(*name, zip::write::FileOptions::default()).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}